@@ -16,28 +16,23 @@ fn main() -> Result<()> {
     journal.register_single(
         NaiveDate::from_ymd(2022, 01, 26),
         "algemeen",
+        "inkomen",
         &amount,
         "Added by example 1/3",
     )?;
     journal.register_single(
         NaiveDate::from_ymd(2022, 01, 27),
         "algemeen",
+        "inkomen",
         &amount,
         "Added by example 2/3",
     )?;
     journal.register_single(
         NaiveDate::from_ymd(2022, 01, 28),
         "algemeen",
-        &amount,
-        "Added by example 3/3",
-    )?;
-
-    let amount = amount * 3;
-    journal.register_single(
-        NaiveDate::from_ymd(2022, 01, 28),
         "inkomen",
         &amount,
-        "Counter all those spendings",
+        "Added by example 3/3",
     )?;
 
     let journal_repository: &mut dyn JournalRepository = &mut ledger;
@@ -0,0 +1,64 @@
+use regex::Regex;
+
+use crate::{ing::DescriptionProperties, SepaDescription};
+
+/// The free text description of an ABN AMRO mutation, split into its parts
+#[derive(Default, Debug)]
+pub struct AbnAmroDescription {
+    pub counter_iban: Option<String>,
+    pub properties: DescriptionProperties,
+}
+
+impl AbnAmroDescription {
+    fn from_sepa(description: &str) -> Self {
+        let sepa = SepaDescription::from(description);
+        Self {
+            counter_iban: sepa.counter_iban,
+            properties: sepa.properties,
+        }
+    }
+
+    fn from_card_payment(description: &str) -> Option<Self> {
+        let card_payment = Regex::new(
+            r"^(BEA|GEA)\s+NR:\S+\s+\d{2}\.\d{2}\.\d{2}/\d{2}[.:]\d{2}\s+(.+?),PAS\s*\d+",
+        )
+        .unwrap();
+
+        card_payment.captures(description).map(|capture| {
+            let mut result = Self::default();
+            result.properties.define_name(capture[2].trim());
+            result
+        })
+    }
+
+    fn from_fixed_width(description: &str) -> Self {
+        let iban = Regex::new(r"IBAN:\s*([A-Z]{2}\d{2}[A-Z0-9]+)").unwrap();
+        let counter_iban = iban
+            .captures(description)
+            .map(|capture| capture[1].to_owned());
+
+        let mut properties = DescriptionProperties::from(description);
+        properties.properties.remove("Incassant");
+        if let Some(contract) = properties.properties.remove("Machtiging") {
+            properties.define_contract(contract);
+        }
+
+        Self {
+            counter_iban,
+            properties,
+        }
+    }
+}
+
+impl From<&str> for AbnAmroDescription {
+    fn from(description: &str) -> Self {
+        let description = description.trim();
+        if description.starts_with("/TRTP/") {
+            Self::from_sepa(description)
+        } else if let Some(card_payment) = Self::from_card_payment(description) {
+            card_payment
+        } else {
+            Self::from_fixed_width(description)
+        }
+    }
+}
@@ -0,0 +1,117 @@
+use std::io::Read;
+
+use serde::Deserialize;
+
+use crate::{
+    bank_formats::conversions::{convert_amount, convert_date, transaction_id},
+    BankImporter, ImportedTransaction, ImportedTransactions,
+};
+
+use super::AbnAmroDescription;
+
+/// One line of the TXT/TAB export, which has no header row
+#[derive(Deserialize, Debug)]
+struct AbnAmroImport {
+    pub account_number: String,
+    pub mutation_code: String,
+    pub transaction_date: String,
+    pub value_date: String,
+    pub start_balance: String,
+    pub end_balance: String,
+    pub amount: String,
+    pub description: String,
+}
+
+/// Importer for the tab separated (TXT/TAB) export of the Dutch ABN AMRO bank
+#[derive(Default)]
+pub struct AbnAmroImporter;
+
+impl BankImporter for AbnAmroImporter {
+    fn read<'r>(&self, rdr: Box<dyn Read + 'r>) -> ImportedTransactions<'r> {
+        let csv_reader = csv::ReaderBuilder::new()
+            .has_headers(false)
+            .delimiter(b'\t')
+            .quoting(false)
+            .flexible(true)
+            .from_reader(rdr);
+
+        Box::new(
+            csv_reader
+                .into_deserialize::<AbnAmroImport>()
+                .enumerate()
+                .map(|(record_nr, abn_amro_import_result)| {
+                    let line_nr = record_nr + 1;
+                    convert_record(line_nr, abn_amro_import_result?)
+                }),
+        )
+    }
+
+    fn confidence(&self, sample: &str) -> f32 {
+        let first_line = sample.lines().next().unwrap_or_default();
+        let fields: Vec<&str> = first_line.split('\t').collect();
+        let is_date = |field: &str| field.len() == 8 && field.chars().all(|c| c.is_ascii_digit());
+
+        if fields.len() == 8 && is_date(fields[2]) && is_date(fields[3]) {
+            0.9
+        } else {
+            0.0
+        }
+    }
+}
+
+/// Older exports only mention the account number, the ledger is configured by IBAN
+fn iban_from_account_number(account_number: &str) -> String {
+    let account_number = account_number.trim();
+    if account_number.is_empty() || !account_number.chars().all(|c| c.is_ascii_digit()) {
+        return account_number.to_owned();
+    }
+
+    let bban = format!("ABNA{:0>10}", account_number);
+    let check_base: String = format!("{}NL00", bban)
+        .chars()
+        .map(|c| c.to_digit(36).unwrap().to_string())
+        .collect();
+    let remainder = check_base.chars().fold(0u32, |remainder, digit| {
+        (remainder * 10 + digit.to_digit(10).unwrap()) % 97
+    });
+
+    format!("NL{:02}{}", 98 - remainder, bban)
+}
+
+fn convert_record(line_nr: usize, record: AbnAmroImport) -> crate::Result<ImportedTransaction> {
+    let date = convert_date(
+        line_nr,
+        "transactiondate",
+        &record.transaction_date,
+        "%Y%m%d",
+    )?;
+    let amount = convert_amount(line_nr, "amount", &record.amount, ',')?;
+    let balance_before = convert_amount(line_nr, "startsaldo", &record.start_balance, ',')?;
+    let balance_after = convert_amount(line_nr, "endsaldo", &record.end_balance, ',')?;
+
+    let hash_base = format!(
+        "|{account}|{code}|{date}|{value_date}|{start}|{end}|{amount}|{description}|",
+        account = record.account_number,
+        code = record.mutation_code,
+        date = record.transaction_date,
+        value_date = record.value_date,
+        start = record.start_balance,
+        end = record.end_balance,
+        amount = record.amount,
+        description = record.description,
+    );
+    let id = transaction_id(&hash_base);
+
+    let description = AbnAmroDescription::from(record.description.as_str());
+
+    Ok(ImportedTransaction {
+        id,
+        iban: iban_from_account_number(&record.account_number),
+        contra_iban: description.counter_iban,
+        date,
+        balance_before: Some(balance_before),
+        amount,
+        balance_after: Some(balance_after),
+        properties: description.properties.properties,
+    })
+}
@@ -0,0 +1,5 @@
+mod import;
+pub use import::*;
+
+mod description;
+pub use description::*;
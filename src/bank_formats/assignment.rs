@@ -0,0 +1,276 @@
+use crate::{
+    conflict, AccountsRepository, AssignmentReason, BankTransaction, CostCentersRepository, Error,
+    RelationsRepository, RuleInput,
+};
+
+use super::{ing::DescriptionProperties, ImportedTransaction};
+
+/// Assigns cost center, relation and account to imported transactions
+pub struct TransactionAssigner<'r> {
+    accounts_repository: &'r dyn AccountsRepository,
+    cost_center_repository: &'r dyn CostCentersRepository,
+    relations_repository: &'r dyn RelationsRepository,
+}
+
+impl<'r> TransactionAssigner<'r> {
+    pub fn new(
+        accounts_repository: &'r dyn AccountsRepository,
+        cost_center_repository: &'r dyn CostCentersRepository,
+        relations_repository: &'r dyn RelationsRepository,
+    ) -> Self {
+        Self {
+            accounts_repository,
+            cost_center_repository,
+            relations_repository,
+        }
+    }
+
+    pub fn assign(&self, imported: ImportedTransaction) -> crate::Result<BankTransaction> {
+        let cost_center = match self
+            .cost_center_repository
+            .find_cost_center_by_iban(&imported.iban)
+        {
+            Some(cost_center) => cost_center,
+            None => return Err(Error::UnrecognisedBankAccount(imported.iban.clone())),
+        };
+
+        let mut attributes = imported.properties;
+        let mut assignment_reason = None;
+        let mut assigned_by = None;
+        let mut account_code = None;
+        let mut splits = vec![];
+
+        let (relation_iban, relation_name_from_iban) = match &imported.contra_iban {
+            Some(counter_iban) => match self
+                .relations_repository
+                .find_relation_by_reference(counter_iban.as_str())
+            {
+                Some(relation) => (Some(counter_iban.clone()), Some(relation.name.clone())),
+                None => (None, None),
+            },
+            None => (None, None),
+        };
+
+        let mut relation_name = match relation_name_from_iban {
+            Some(relation_name_from_iban) => Some(relation_name_from_iban),
+            None => attributes.remove(DescriptionProperties::NAME),
+        };
+
+        let input = RuleInput {
+            cost_center: &cost_center,
+            relation_name: relation_name.as_deref(),
+            counter_iban: imported.contra_iban.as_deref(),
+            description: attributes
+                .get(DescriptionProperties::DESCRIPTION)
+                .map(String::as_str),
+            attributes: &attributes,
+            amount: imported.amount,
+            date: imported.date,
+        };
+        let matches = self.accounts_repository.find_assignment_rules(&input);
+        let assignment_warning = conflict(&matches).map(|e| e.to_string());
+        let found = self
+            .accounts_repository
+            .assignment_policy()
+            .choose(&matches)
+            .map(|(rule, rule_match)| (*rule, rule_match));
+        attributes.remove(DescriptionProperties::TAG);
+
+        if let Some((rule, rule_match)) = found {
+            account_code = Some(rule.account_code.clone());
+            splits = rule.splits.clone();
+            assignment_reason = Some(rule.reason.clone());
+            assigned_by = Some(rule.assigned_by(rule_match));
+            // The search term finds the full name in what the bank gives
+            if rule.reason == AssignmentReason::RelationName {
+                relation_name = rule_match
+                    .matched
+                    .iter()
+                    .find(|matched| matched.field == "relation")
+                    .map(|matched| matched.text.clone())
+            }
+
+            // As before the priorities: a known contract is taken out of the attributes and
+            // prefixes the description, even when a description rule assigns the account
+            let contract_rule = matches
+                .iter()
+                .map(|(rule, _)| *rule)
+                .find(|rule| rule.reason == AssignmentReason::Contract);
+            if contract_rule.is_some() {
+                attributes.remove(DescriptionProperties::CONTRACT);
+            }
+            let prefixing_rule = match rule.reason {
+                AssignmentReason::Rule => Some(rule),
+                _ => contract_rule,
+            };
+
+            if let Some(proposed) = prefixing_rule.and_then(|rule| rule.description.as_ref()) {
+                let description = match attributes.get(DescriptionProperties::DESCRIPTION) {
+                    Some(existing) => format!("{} {}", proposed, existing),
+                    None => proposed.clone(),
+                };
+                attributes.insert(DescriptionProperties::DESCRIPTION.to_string(), description);
+            }
+        }
+
+        Ok(BankTransaction {
+            id: imported.id,
+            iban: imported.iban,
+            date: imported.date,
+            cost_center,
+            relation_iban,
+            relation_name,
+            attributes,
+            amount: imported.amount,
+            balance_before: imported.balance_before,
+            balance_after: imported.balance_after,
+            account_code,
+            assignment_reason,
+            assigned_by,
+            assignment_warning,
+            splits,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use chrono::NaiveDate;
+
+    use super::*;
+    use crate::{
+        sort_by_priority, Account, AssignByContractDefinition, AssignByDescriptionDefinition,
+        AssignmentPolicy, AssignmentRule, AssignmentRuleDefinition, Relation, RuleInput, RuleMatch,
+    };
+
+    /// A ledger with nothing but the rules
+    struct Rules(Vec<AssignmentRule>);
+
+    impl AccountsRepository for Rules {
+        fn find_account_by_reference(&self, _reference: &str) -> Option<&Account> {
+            None
+        }
+
+        fn find_bank_account_code(&self, _iban: &str) -> Option<&str> {
+            None
+        }
+
+        fn find_assignment_rules(&self, input: &RuleInput) -> Vec<(&AssignmentRule, RuleMatch)> {
+            self.0
+                .iter()
+                .filter_map(|rule| Some((rule, rule.matches(input)?)))
+                .collect()
+        }
+
+        fn assignment_policy(&self) -> AssignmentPolicy {
+            AssignmentPolicy::First
+        }
+    }
+
+    impl CostCentersRepository for Rules {
+        fn find_cost_center_by_iban(&self, _iban: &str) -> Option<String> {
+            Some("Hers".to_string())
+        }
+
+        fn cost_centers(&self) -> Vec<&str> {
+            vec!["Hers"]
+        }
+    }
+
+    impl RelationsRepository for Rules {
+        fn find_relation_by_reference(&self, _reference: &str) -> Option<&Relation> {
+            None
+        }
+    }
+
+    fn assign(rules: Vec<AssignmentRule>, contract: &str) -> BankTransaction {
+        let mut rules = Rules(rules);
+        sort_by_priority(&mut rules.0);
+        let imported = ImportedTransaction {
+            id: "t1".to_string(),
+            iban: "NL91ABNA0417164300".to_string(),
+            contra_iban: None,
+            date: NaiveDate::from_ymd_opt(2022, 1, 3).unwrap(),
+            balance_before: None,
+            amount: crate::Amount::new(-1250, 2),
+            balance_after: None,
+            properties: HashMap::from([
+                (DescriptionProperties::NAME.to_string(), "Eneco".to_string()),
+                (
+                    DescriptionProperties::DESCRIPTION.to_string(),
+                    "Energie jan".to_string(),
+                ),
+                (
+                    DescriptionProperties::CONTRACT.to_string(),
+                    contract.to_string(),
+                ),
+            ]),
+        };
+
+        TransactionAssigner::new(&rules, &rules, &rules)
+            .assign(imported)
+            .unwrap()
+    }
+
+    fn contract_rule() -> AssignmentRule {
+        let contract: AssignByContractDefinition =
+            serde_yaml::from_str("{account: by_contract, description: Energy}").unwrap();
+        AssignmentRule::by_contract("MND123", &contract, DescriptionProperties::CONTRACT).unwrap()
+    }
+
+    fn description(transaction: &BankTransaction) -> Option<&str> {
+        transaction
+            .attributes
+            .get(DescriptionProperties::DESCRIPTION)
+            .map(String::as_str)
+    }
+
+    #[test]
+    fn only_a_known_contract_prefixes_the_description() {
+        let description_rule: AssignByDescriptionDefinition =
+            serde_yaml::from_str("{account: by_description, search: energie}").unwrap();
+        let rules = || {
+            vec![
+                contract_rule(),
+                AssignmentRule::by_description("Hers & Eneco", &description_rule).unwrap(),
+            ]
+        };
+
+        // The description rule wins, the contract is still taken out
+        let transaction = assign(rules(), "MND123");
+        assert_eq!(transaction.account_code.as_deref(), Some("by_description"));
+        assert_eq!(description(&transaction), Some("Energy Energie jan"));
+        assert!(!transaction
+            .attributes
+            .contains_key(DescriptionProperties::CONTRACT));
+
+        let transaction = assign(rules(), "OTHER");
+        assert_eq!(transaction.account_code.as_deref(), Some("by_description"));
+        assert_eq!(description(&transaction), Some("Energie jan"));
+        assert_eq!(
+            transaction
+                .attributes
+                .get(DescriptionProperties::CONTRACT)
+                .map(String::as_str),
+            Some("OTHER")
+        );
+    }
+
+    #[test]
+    fn a_winning_assignment_rule_prefixes_its_own_description() {
+        let definition: AssignmentRuleDefinition = serde_yaml::from_str(
+            "{account: by_rule, priority: 500, description: Gas, when: {relation: Eneco}}",
+        )
+        .unwrap();
+        let rules = vec![
+            contract_rule(),
+            AssignmentRule::from_definition(0, &definition).unwrap(),
+        ];
+
+        let transaction = assign(rules, "MND123");
+        assert_eq!(transaction.account_code.as_deref(), Some("by_rule"));
+        assert_eq!(description(&transaction), Some("Gas Energie jan"));
+    }
+}
@@ -0,0 +1,324 @@
+use std::{
+    collections::{BTreeMap, HashSet},
+    fmt::Display,
+};
+
+use chrono::NaiveDate;
+use rust_decimal::Decimal;
+use serde::Serialize;
+
+use crate::{BankTransaction, BankTransactions};
+
+/// A break in the balances of a bank account, found while importing
+#[derive(Serialize, Debug, Clone, PartialEq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ContinuityWarning {
+    /// The balance before a transaction is not the balance after its predecessor,
+    /// so transactions (or a whole export) between the two dates are missing
+    Gap {
+        iban: String,
+        from: NaiveDate,
+        until: NaiveDate,
+        expected: Decimal,
+        found: Decimal,
+    },
+    /// The upload covers a period of which transactions were imported before
+    Overlap {
+        iban: String,
+        from: NaiveDate,
+        until: NaiveDate,
+        already_imported: usize,
+    },
+}
+
+impl Display for ContinuityWarning {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ContinuityWarning::Gap {
+                iban,
+                from,
+                until,
+                expected,
+                found,
+            } => write!(
+                f,
+                "Missing transactions of {} between {} and {}: balance {} is followed by {}",
+                iban, from, until, expected, found
+            ),
+            ContinuityWarning::Overlap {
+                iban,
+                from,
+                until,
+                already_imported,
+            } => write!(
+                f,
+                "Upload of {} overlaps earlier imports from {} until {} ({} already imported)",
+                iban, from, until, already_imported
+            ),
+        }
+    }
+}
+
+/// Checks that the balances of the uploaded transactions follow each other
+/// and connect to the transactions imported before
+///
+/// Must be called before the uploaded transactions are added to `imported`.
+pub fn verify_continuity(
+    uploaded: &[BankTransaction],
+    imported: &BankTransactions,
+) -> Vec<ContinuityWarning> {
+    let mut per_account: BTreeMap<&str, Vec<&BankTransaction>> = BTreeMap::new();
+    for transaction in uploaded {
+        per_account
+            .entry(transaction.iban.as_str())
+            .or_default()
+            .push(transaction);
+    }
+
+    let mut warnings = vec![];
+    for (iban, transactions) in per_account {
+        let uploaded = in_sequence(transactions);
+        let uploaded_ids: HashSet<&str> = uploaded.iter().map(|t| t.id.as_str()).collect();
+        let earlier: Vec<&BankTransaction> = imported
+            .iter()
+            .filter(|t| t.iban == iban && !uploaded_ids.contains(t.id.as_str()))
+            .collect();
+
+        verify_sequence(iban, &uploaded, &mut warnings);
+        verify_connection(iban, &uploaded, &earlier, imported, &mut warnings);
+    }
+
+    warnings
+}
+
+/// Exports list the transactions either oldest or newest first; this
+/// returns them oldest first, keeping the order of the export within a day
+fn in_sequence(mut transactions: Vec<&BankTransaction>) -> Vec<&BankTransaction> {
+    if let (Some(first), Some(last)) = (transactions.first(), transactions.last()) {
+        if first.date > last.date {
+            transactions.reverse();
+        }
+    }
+    transactions.sort_by_key(|transaction| transaction.date);
+    transactions
+}
+
+fn verify_sequence(
+    iban: &str,
+    uploaded: &[&BankTransaction],
+    warnings: &mut Vec<ContinuityWarning>,
+) {
+    for pair in uploaded.windows(2) {
+        if let (Some(expected), Some(found)) = (pair[0].balance_after, pair[1].balance_before) {
+            if expected != found {
+                warnings.push(ContinuityWarning::Gap {
+                    iban: iban.to_string(),
+                    from: pair[0].date,
+                    until: pair[1].date,
+                    expected,
+                    found,
+                });
+            }
+        }
+    }
+}
+
+fn verify_connection(
+    iban: &str,
+    uploaded: &[&BankTransaction],
+    earlier: &[&BankTransaction],
+    imported: &BankTransactions,
+    warnings: &mut Vec<ContinuityWarning>,
+) {
+    let (first, last) = match (uploaded.first(), uploaded.last()) {
+        (Some(first), Some(last)) => (first, last),
+        _ => return,
+    };
+
+    let already_imported: Vec<&&BankTransaction> = uploaded
+        .iter()
+        .filter(|transaction| imported.get(&transaction.id).is_some())
+        .collect();
+    let earliest = earlier.iter().map(|t| t.date).min();
+    let latest = earlier.iter().map(|t| t.date).max();
+
+    let overlaps = match (earliest, latest) {
+        (Some(earliest), Some(latest)) => first.date < latest && last.date > earliest,
+        _ => false,
+    };
+    if overlaps || !already_imported.is_empty() {
+        let dates = already_imported.iter().map(|t| t.date);
+        warnings.push(ContinuityWarning::Overlap {
+            iban: iban.to_string(),
+            from: dates.clone().min().unwrap_or(first.date),
+            until: dates.max().unwrap_or(last.date),
+            already_imported: already_imported.len(),
+        });
+        return;
+    }
+
+    if let Some(latest) = latest.filter(|latest| *latest <= first.date) {
+        let last_day: Vec<&BankTransaction> = earlier
+            .iter()
+            .copied()
+            .filter(|t| t.date == latest)
+            .collect();
+        if let (Some(expected), Some(found)) = (closing_balance(&last_day), first.balance_before) {
+            if expected != found {
+                warnings.push(ContinuityWarning::Gap {
+                    iban: iban.to_string(),
+                    from: latest,
+                    until: first.date,
+                    expected,
+                    found,
+                });
+            }
+        }
+    } else if let Some(earliest) = earliest.filter(|earliest| *earliest >= last.date) {
+        let first_day: Vec<&BankTransaction> = earlier
+            .iter()
+            .copied()
+            .filter(|t| t.date == earliest)
+            .collect();
+        if let (Some(expected), Some(found)) = (last.balance_after, opening_balance(&first_day)) {
+            if expected != found {
+                warnings.push(ContinuityWarning::Gap {
+                    iban: iban.to_string(),
+                    from: last.date,
+                    until: earliest,
+                    expected,
+                    found,
+                });
+            }
+        }
+    }
+}
+
+/// Balance after the last of the transactions of a single day, which is the
+/// one whose balance is not continued by another transaction of that day
+fn closing_balance(day: &[&BankTransaction]) -> Option<Decimal> {
+    let continued: HashSet<Decimal> = day.iter().filter_map(|t| t.balance_before).collect();
+    day.iter()
+        .filter_map(|t| t.balance_after)
+        .find(|balance| !continued.contains(balance))
+}
+
+/// Balance before the first of the transactions of a single day
+fn opening_balance(day: &[&BankTransaction]) -> Option<Decimal> {
+    let continuing: HashSet<Decimal> = day.iter().filter_map(|t| t.balance_after).collect();
+    day.iter()
+        .filter_map(|t| t.balance_before)
+        .find(|balance| !continuing.contains(balance))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+
+    const IBAN: &str = "NL91ABNA0417164300";
+
+    fn transaction(id: &str, day: u32, before: i64, after: i64) -> BankTransaction {
+        BankTransaction {
+            id: id.to_string(),
+            iban: IBAN.to_string(),
+            date: NaiveDate::from_ymd_opt(2022, 3, day).unwrap(),
+            cost_center: "Hers".to_string(),
+            relation_name: None,
+            relation_iban: None,
+            attributes: HashMap::new(),
+            amount: Decimal::new(after - before, 2),
+            balance_before: Some(Decimal::new(before, 2)),
+            balance_after: Some(Decimal::new(after, 2)),
+            account_code: None,
+            assignment_reason: None,
+            assigned_by: None,
+            assignment_warning: None,
+            splits: vec![],
+        }
+    }
+
+    fn imported(transactions: Vec<BankTransaction>) -> BankTransactions {
+        let mut imported = BankTransactions::default();
+        for transaction in transactions {
+            imported.import(transaction);
+        }
+        imported
+    }
+
+    #[test]
+    fn following_balances_give_no_warnings() {
+        let earlier = imported(vec![transaction("a", 1, 1000, 900)]);
+        // Newest first, as some banks export
+        let uploaded = vec![transaction("c", 3, 850, 800), transaction("b", 2, 900, 850)];
+
+        assert_eq!(verify_continuity(&uploaded, &earlier), vec![]);
+    }
+
+    #[test]
+    fn gap_within_the_upload() {
+        let uploaded = vec![
+            transaction("a", 1, 1000, 900),
+            transaction("c", 5, 850, 800),
+        ];
+
+        assert_eq!(
+            verify_continuity(&uploaded, &BankTransactions::default()),
+            vec![ContinuityWarning::Gap {
+                iban: IBAN.to_string(),
+                from: NaiveDate::from_ymd_opt(2022, 3, 1).unwrap(),
+                until: NaiveDate::from_ymd_opt(2022, 3, 5).unwrap(),
+                expected: Decimal::new(900, 2),
+                found: Decimal::new(850, 2),
+            }]
+        );
+    }
+
+    #[test]
+    fn gap_with_the_earlier_imports() {
+        // The last of the same day is the one that is not continued
+        let earlier = imported(vec![
+            transaction("b", 2, 900, 875),
+            transaction("a", 2, 1000, 900),
+        ]);
+        let uploaded = vec![transaction("d", 9, 850, 800)];
+
+        assert_eq!(
+            verify_continuity(&uploaded, &earlier),
+            vec![ContinuityWarning::Gap {
+                iban: IBAN.to_string(),
+                from: NaiveDate::from_ymd_opt(2022, 3, 2).unwrap(),
+                until: NaiveDate::from_ymd_opt(2022, 3, 9).unwrap(),
+                expected: Decimal::new(875, 2),
+                found: Decimal::new(850, 2),
+            }]
+        );
+
+        // An upload of an earlier period must end where the imports start
+        let uploaded = vec![transaction("z", 1, 1100, 1050)];
+        assert!(matches!(
+            verify_continuity(&uploaded, &earlier)[..],
+            [ContinuityWarning::Gap { found, .. }] if found == Decimal::new(1000, 2)
+        ));
+    }
+
+    #[test]
+    fn overlap_with_the_earlier_imports() {
+        let earlier = imported(vec![
+            transaction("a", 1, 1000, 900),
+            transaction("b", 2, 900, 850),
+        ]);
+        let uploaded = vec![transaction("b", 2, 900, 850), transaction("c", 3, 850, 800)];
+
+        assert_eq!(
+            verify_continuity(&uploaded, &earlier),
+            vec![ContinuityWarning::Overlap {
+                iban: IBAN.to_string(),
+                from: NaiveDate::from_ymd_opt(2022, 3, 2).unwrap(),
+                until: NaiveDate::from_ymd_opt(2022, 3, 2).unwrap(),
+                already_imported: 1,
+            }]
+        );
+    }
+}
@@ -0,0 +1,105 @@
+use std::{collections::HashMap, fmt::Debug};
+
+use serde::Serialize;
+use tracing::warn;
+
+use crate::{
+    abn_amro::AbnAmroImporter, camt053::Camt053Importer, generic_csv::CsvImporter,
+    ing::IngImporter, mt940::Mt940Importer, ofx::OfxImporter, rabobank::RabobankImporter,
+    BankFormat, BankImporter, Error,
+};
+
+/// The importers for the bank formats that are configured in the ledger
+#[derive(Default)]
+pub struct BankImporters {
+    importers: HashMap<String, Box<dyn BankImporter>>,
+}
+
+impl BankImporters {
+    /// Formats without importer are skipped, uploading with them fails
+    pub fn from_formats(formats: &HashMap<String, BankFormat>) -> crate::Result<Self> {
+        let mut importers = HashMap::new();
+        for (format_code, format) in formats.iter() {
+            let importer = match &format.csv_mapping {
+                Some(csv_mapping) => {
+                    Box::new(CsvImporter::try_new(format_code, csv_mapping.clone())?)
+                }
+                None => match builtin_importer(format_code) {
+                    Some(importer) => importer,
+                    None => {
+                        warn!("{}", Error::UnrecognisedBankFormat(format_code.to_owned()));
+                        continue;
+                    }
+                },
+            };
+            importers.insert(format_code.to_owned(), importer);
+        }
+
+        Ok(Self { importers })
+    }
+
+    pub fn find(&self, format_code: &str) -> Option<&dyn BankImporter> {
+        self.importers
+            .get(format_code)
+            .map(|importer| importer.as_ref())
+    }
+
+    /// The configured formats that the contents look like, best match first
+    pub fn detect(&self, contents: &[u8]) -> Vec<DetectedFormat> {
+        let sample = contents_sample(contents);
+        let mut detected: Vec<DetectedFormat> = self
+            .importers
+            .iter()
+            .map(|(format_code, importer)| DetectedFormat {
+                format_code: format_code.clone(),
+                confidence: importer.confidence(&sample),
+            })
+            .filter(|detected| detected.confidence >= DetectedFormat::MINIMAL_CONFIDENCE)
+            .collect();
+
+        detected.sort_by(|one, other| {
+            other
+                .confidence
+                .total_cmp(&one.confidence)
+                .then_with(|| one.format_code.cmp(&other.format_code))
+        });
+        detected
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DetectedFormat {
+    pub format_code: String,
+    pub confidence: f32,
+}
+
+impl DetectedFormat {
+    pub const MINIMAL_CONFIDENCE: f32 = 0.5;
+}
+
+/// The start of the uploaded file, enough to recognise its format
+pub fn contents_sample(contents: &[u8]) -> String {
+    const SAMPLE_SIZE: usize = 8 * 1024;
+
+    let sample = &contents[..usize::min(contents.len(), SAMPLE_SIZE)];
+    let sample = sample.strip_prefix(b"\xEF\xBB\xBF").unwrap_or(sample);
+    String::from_utf8_lossy(sample).into_owned()
+}
+
+fn builtin_importer(format_code: &str) -> Option<Box<dyn BankImporter>> {
+    match format_code.to_ascii_lowercase().as_str() {
+        "ing" => Some(Box::new(IngImporter::default())),
+        "rabobank" => Some(Box::new(RabobankImporter)),
+        "abn_amro" => Some(Box::new(AbnAmroImporter)),
+        "camt053" => Some(Box::new(Camt053Importer)),
+        "mt940" => Some(Box::new(Mt940Importer)),
+        "ofx" => Some(Box::new(OfxImporter)),
+        _ => None,
+    }
+}
+
+impl Debug for BankImporters {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_list().entries(self.importers.keys()).finish()
+    }
+}
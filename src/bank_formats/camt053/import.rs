@@ -0,0 +1,455 @@
+use std::io::Read;
+
+use roxmltree::{Document, Node};
+
+use crate::{
+    bank_formats::conversions::{convert_amount, convert_date, transaction_id},
+    ing::DescriptionProperties,
+    Amount, BankImporter, Error, ImportedTransaction, ImportedTransactions,
+};
+
+/// Importer for ISO 20022 CAMT.053 bank to customer statements
+#[derive(Default)]
+pub struct Camt053Importer;
+
+impl BankImporter for Camt053Importer {
+    fn read<'r>(&self, mut rdr: Box<dyn Read + 'r>) -> ImportedTransactions<'r> {
+        let mut xml = String::new();
+        if let Err(e) = rdr.read_to_string(&mut xml) {
+            return Box::new(std::iter::once(Err(Error::from(e))));
+        }
+
+        let results = match Document::parse(&xml) {
+            Ok(document) => read_document(&document),
+            Err(e) => vec![Err(Error::from(e))],
+        };
+
+        Box::new(results.into_iter())
+    }
+
+    fn confidence(&self, sample: &str) -> f32 {
+        if sample.contains("urn:iso:std:iso:20022:tech:xsd:camt.053") {
+            0.99
+        } else if sample.contains("BkToCstmrStmt") {
+            0.9
+        } else {
+            0.0
+        }
+    }
+}
+
+/// The first descendant that follows the path of (namespace less) element names
+fn child<'a, 'input>(node: Node<'a, 'input>, path: &[&str]) -> Option<Node<'a, 'input>> {
+    path.iter().try_fold(node, |parent, name| {
+        parent
+            .children()
+            .find(|child| child.is_element() && child.tag_name().name() == *name)
+    })
+}
+
+fn children<'a, 'input>(
+    node: Node<'a, 'input>,
+    name: &'a str,
+) -> impl Iterator<Item = Node<'a, 'input>> {
+    node.children()
+        .filter(move |child| child.is_element() && child.tag_name().name() == name)
+}
+
+fn text<'a>(node: Node<'a, '_>, path: &[&str]) -> Option<&'a str> {
+    child(node, path)
+        .and_then(|found| found.text())
+        .map(|found| found.trim())
+        .filter(|found| !found.is_empty())
+}
+
+fn line_nr(node: Node) -> usize {
+    node.document().text_pos_at(node.range().start).row as usize
+}
+
+fn amount(node: Node, amount_path: &[&str], field: &'static str) -> crate::Result<Amount> {
+    let line_nr = line_nr(node);
+    let amount_text = text(node, amount_path).ok_or(Error::RecordConversionFailed {
+        line_nr,
+        field,
+        reason: "is missing".to_string(),
+    })?;
+
+    convert_amount(line_nr, field, amount_text, '.')
+}
+
+/// Amount of the node, negative when the first credit/debit indicator found says it is a debit
+fn signed_amount(
+    node: Node,
+    amount_path: &[&str],
+    field: &'static str,
+    indicators: &[Node],
+) -> crate::Result<Amount> {
+    let amount = amount(node, amount_path, field)?;
+
+    Ok(
+        match indicators
+            .iter()
+            .find_map(|indicator| text(*indicator, &["CdtDbtInd"]))
+        {
+            Some("DBIT") => -amount,
+            _ => amount,
+        },
+    )
+}
+
+fn date(node: Node, path: &[&str], field: &'static str) -> crate::Result<chrono::NaiveDate> {
+    let line_nr = line_nr(node);
+    let mut date_path = path.to_vec();
+    date_path.push("Dt");
+    let date_text = match text(node, &date_path) {
+        Some(date_text) => date_text,
+        None => {
+            date_path.pop();
+            date_path.push("DtTm");
+            text(node, &date_path)
+                .and_then(|date_time| date_time.get(..10))
+                .ok_or(Error::RecordConversionFailed {
+                    line_nr,
+                    field,
+                    reason: "is missing".to_string(),
+                })?
+        }
+    };
+
+    convert_date(line_nr, field, date_text, "%Y-%m-%d")
+}
+
+fn balance(statement: Node, balance_codes: &[&str]) -> crate::Result<Option<Amount>> {
+    match balance_node(statement, balance_codes) {
+        Some(balance) => signed_amount(balance, &["Amt"], "Bal", &[balance]).map(Some),
+        None => Ok(None),
+    }
+}
+
+/// The first balance of the statement with one of the codes, in order of preference
+fn balance_node<'a, 'input>(
+    statement: Node<'a, 'input>,
+    balance_codes: &[&str],
+) -> Option<Node<'a, 'input>> {
+    balance_codes.iter().find_map(|balance_code| {
+        children(statement, "Bal")
+            .find(|bal| text(*bal, &["Tp", "CdOrPrtry", "Cd"]) == Some(*balance_code))
+    })
+}
+
+fn read_document(document: &Document) -> Vec<crate::Result<ImportedTransaction>> {
+    let mut results = vec![];
+
+    for statement in document
+        .descendants()
+        .filter(|node| node.is_element() && node.tag_name().name() == "Stmt")
+    {
+        if let Err(e) = read_statement(statement, &mut results) {
+            results.push(Err(e));
+        }
+    }
+
+    results
+}
+
+fn read_statement(
+    statement: Node,
+    results: &mut Vec<crate::Result<ImportedTransaction>>,
+) -> crate::Result<()> {
+    let iban = text(statement, &["Acct", "Id", "IBAN"]).ok_or(Error::RecordConversionFailed {
+        line_nr: line_nr(statement),
+        field: "Acct",
+        reason: "has no IBAN".to_string(),
+    })?;
+
+    let opening = balance(statement, &["OPBD", "PRCD"])?;
+    let closing = balance(statement, &["CLBD"])?;
+
+    let mut running_balance = opening;
+    let mut total = Some(Amount::ZERO);
+
+    for entry in children(statement, "Ntry") {
+        // Without its amount the balances after the entry are unknown
+        let entry_amount = match signed_amount(entry, &["Amt"], "Ntry/Amt", &[entry]) {
+            Ok(entry_amount) => entry_amount,
+            Err(e) => {
+                running_balance = None;
+                total = None;
+                results.push(Err(e));
+                continue;
+            }
+        };
+        total = total.map(|total| total + entry_amount);
+
+        let details: Vec<Node> = child(entry, &["NtryDtls"])
+            .map(|entry_details| children(entry_details, "TxDtls").collect())
+            .unwrap_or_default();
+
+        // A batch booking without individual amounts is a single transaction
+        let has_amounts = details
+            .iter()
+            .any(|transaction_details| details_amount(*transaction_details, entry).is_some());
+        let batch_booking = details.len() > 1;
+        if batch_booking && has_amounts {
+            if let Err(e) = check_details_amounts(entry, &details, entry_amount) {
+                running_balance = running_balance.map(|balance| balance + entry_amount);
+                results.push(Err(e));
+                continue;
+            }
+            for (index, transaction_details) in details.into_iter().enumerate() {
+                let result = convert_entry(
+                    iban,
+                    entry,
+                    Some(transaction_details),
+                    index,
+                    &mut running_balance,
+                );
+                results.push(result);
+            }
+        } else {
+            let result = convert_entry(
+                iban,
+                entry,
+                details.into_iter().next().filter(|_| !batch_booking),
+                0,
+                &mut running_balance,
+            );
+            results.push(result);
+        }
+    }
+
+    if let (Some(opening), Some(closing), Some(total)) = (opening, closing, total) {
+        if opening + total != closing {
+            return Err(Error::StatementBalanceMismatch {
+                iban: iban.to_owned(),
+                tag: "CLBD".to_string(),
+                line_nr: balance_node(statement, &["CLBD"]).map_or(line_nr(statement), line_nr),
+                opening,
+                closing,
+                calculated: opening + total,
+            });
+        }
+    }
+
+    Ok(())
+}
+
+fn convert_entry(
+    iban: &str,
+    entry: Node,
+    transaction_details: Option<Node>,
+    index: usize,
+    running_balance: &mut Option<Amount>,
+) -> crate::Result<ImportedTransaction> {
+    let date = date(entry, &["BookgDt"], "BookgDt")?;
+    let value_date = date_text(entry, "ValDt");
+
+    let amount = match transaction_details.and_then(|details| details_amount(details, entry)) {
+        Some(amount) => amount?,
+        None => signed_amount(entry, &["Amt"], "Ntry/Amt", &[entry])?,
+    };
+
+    let balance_before = *running_balance;
+    let balance_after = balance_before.map(|balance| balance + amount);
+    *running_balance = balance_after;
+
+    let mut props = DescriptionProperties::default();
+    let mut contra_iban = None;
+    let mut end_to_end_id = None;
+
+    if let Some(details) = transaction_details {
+        let (counterparty, counterparty_account) = if amount < Amount::ZERO {
+            ("Cdtr", "CdtrAcct")
+        } else {
+            ("Dbtr", "DbtrAcct")
+        };
+
+        if let Some(name) = text(details, &["RltdPties", counterparty, "Nm"]) {
+            props.define_name(name);
+        }
+        contra_iban = text(details, &["RltdPties", counterparty_account, "Id", "IBAN"])
+            .map(|iban| iban.to_owned());
+
+        if let Some(mandate_id) = text(details, &["Refs", "MndtId"]) {
+            props.define_contract(mandate_id.to_owned());
+        }
+
+        end_to_end_id = text(details, &["Refs", "EndToEndId"])
+            .filter(|id| !id.eq_ignore_ascii_case("NOTPROVIDED"));
+
+        let reference =
+            end_to_end_id.or_else(|| text(details, &["RmtInf", "Strd", "CdtrRefInf", "Ref"]));
+        if let Some(reference) = reference {
+            props.define_reference(reference.to_owned());
+        }
+
+        let unstructured: Vec<&str> = child(details, &["RmtInf"])
+            .map(|remittance| {
+                children(remittance, "Ustrd")
+                    .filter_map(|ustrd| ustrd.text())
+                    .map(|ustrd| ustrd.trim())
+                    .collect()
+            })
+            .unwrap_or_default();
+        if !unstructured.is_empty() {
+            props.define_description(unstructured.join(" "));
+        } else if let Some(description) =
+            text(details, &["AddtlTxInf"]).or_else(|| text(entry, &["AddtlNtryInf"]))
+        {
+            props.define_description(description.to_owned());
+        }
+    } else if let Some(description) = text(entry, &["AddtlNtryInf"]) {
+        props.define_description(description.to_owned());
+    }
+
+    let hash_base = format!(
+        "|{iban}|{date}|{value_date}|{amount}|{servicer_ref}|{end_to_end}|{index}|{counter_iban}|{name} {description}|",
+        date = date,
+        value_date = value_date.unwrap_or_default(),
+        servicer_ref = text(entry, &["AcctSvcrRef"]).unwrap_or_default(),
+        end_to_end = end_to_end_id.unwrap_or_default(),
+        counter_iban = contra_iban.as_deref().unwrap_or_default(),
+        name = props
+            .properties
+            .get(DescriptionProperties::NAME)
+            .map(String::as_str)
+            .unwrap_or_default(),
+        description = props.description().map(String::as_str).unwrap_or_default(),
+    );
+    let id = transaction_id(&hash_base);
+
+    Ok(ImportedTransaction {
+        id,
+        iban: iban.to_owned(),
+        contra_iban,
+        date,
+        balance_before,
+        amount,
+        balance_after,
+        properties: props.properties,
+    })
+}
+
+/// Batch bookings carry the individual amounts in their transaction details
+fn details_amount(details: Node, entry: Node) -> Option<crate::Result<Amount>> {
+    if text(details, &["AmtDtls", "TxAmt", "Amt"]).is_some() {
+        Some(signed_amount(
+            details,
+            &["AmtDtls", "TxAmt", "Amt"],
+            "TxAmt",
+            &[details, entry],
+        ))
+    } else if text(details, &["Amt"]).is_some() {
+        Some(signed_amount(
+            details,
+            &["Amt"],
+            "TxDtls/Amt",
+            &[details, entry],
+        ))
+    } else {
+        None
+    }
+}
+
+/// The amounts of the transaction details of a batch booking must add up to the
+/// amount of the entry
+fn check_details_amounts(entry: Node, details: &[Node], entry_amount: Amount) -> crate::Result<()> {
+    let mut sum = Amount::ZERO;
+    for transaction_details in details {
+        match details_amount(*transaction_details, entry) {
+            Some(amount) => sum += amount?,
+            None => {
+                return Err(Error::RecordConversionFailed {
+                    line_nr: line_nr(*transaction_details),
+                    field: "TxDtls",
+                    reason: "has no amount while other details of the entry have".to_string(),
+                })
+            }
+        }
+    }
+
+    if sum != entry_amount {
+        return Err(Error::RecordConversionFailed {
+            line_nr: line_nr(entry),
+            field: "TxDtls",
+            reason: format!(
+                "the amounts of the details sum up to {} instead of the entry amount {}",
+                sum, entry_amount
+            ),
+        });
+    }
+    Ok(())
+}
+
+fn date_text<'a>(entry: Node<'a, '_>, name: &str) -> Option<&'a str> {
+    text(entry, &[name, "Dt"]).or_else(|| text(entry, &[name, "DtTm"]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn statement(details: &str) -> String {
+        format!(
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<Document xmlns="urn:iso:std:iso:20022:tech:xsd:camt.053.001.02"><BkToCstmrStmt>
+<Stmt><Acct><Id><IBAN>NL91ABNA0417164300</IBAN></Id></Acct>
+<Ntry><Amt Ccy="EUR">950.00</Amt><CdtDbtInd>CRDT</CdtDbtInd><BookgDt><Dt>2022-01-25</Dt></BookgDt>
+<NtryDtls>{}</NtryDtls></Ntry>
+</Stmt></BkToCstmrStmt></Document>"#,
+            details
+        )
+    }
+
+    fn import(details: &str) -> Vec<crate::Result<ImportedTransaction>> {
+        let content = statement(details);
+        Camt053Importer.read(Box::new(content.as_bytes())).collect()
+    }
+
+    #[test]
+    fn batch_booking_without_amounts_is_one_transaction() {
+        let transactions = import(
+            "<TxDtls><RmtInf><Ustrd>One</Ustrd></RmtInf></TxDtls>\
+             <TxDtls><RmtInf><Ustrd>Two</Ustrd></RmtInf></TxDtls>",
+        );
+
+        assert_eq!(transactions.len(), 1);
+        assert_eq!(
+            transactions[0].as_ref().unwrap().amount,
+            Amount::new(95000, 2)
+        );
+    }
+
+    #[test]
+    fn batch_booking_amounts_must_add_up() {
+        let transactions = import(
+            "<TxDtls><AmtDtls><TxAmt><Amt Ccy=\"EUR\">900.00</Amt></TxAmt></AmtDtls></TxDtls>\
+             <TxDtls><AmtDtls><TxAmt><Amt Ccy=\"EUR\">40.00</Amt></TxAmt></AmtDtls></TxDtls>",
+        );
+
+        assert_eq!(transactions.len(), 1);
+        assert!(matches!(
+            transactions[0],
+            Err(Error::RecordConversionFailed {
+                field: "TxDtls",
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn malformed_entry_does_not_abort_the_statement() {
+        let content = statement("").replace(
+            "<Ntry>",
+            "<Ntry><Amt Ccy=\"EUR\">n/a</Amt><CdtDbtInd>DBIT</CdtDbtInd></Ntry>\n<Ntry>",
+        );
+        let transactions: Vec<_> = Camt053Importer.read(Box::new(content.as_bytes())).collect();
+
+        assert_eq!(transactions.len(), 2);
+        assert!(transactions[0].is_err());
+        assert_eq!(
+            transactions[1].as_ref().unwrap().amount,
+            Amount::new(95000, 2)
+        );
+    }
+}
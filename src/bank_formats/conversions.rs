@@ -0,0 +1,201 @@
+use chrono::NaiveDate;
+use rust_decimal::Decimal;
+use sha2::{Digest, Sha256};
+
+use crate::Amount;
+
+pub(crate) fn convert_date(
+    line_nr: usize,
+    field: &'static str,
+    date_as_text: &str,
+    date_format: &str,
+) -> crate::Result<NaiveDate> {
+    match NaiveDate::parse_from_str(date_as_text, date_format) {
+        Ok(date) => Ok(date),
+        Err(_) => Err(crate::Error::RecordConversionFailed {
+            line_nr,
+            field,
+            reason: format!(
+                "'{}' does not match the date format '{}'",
+                date_as_text, date_format
+            ),
+        }),
+    }
+}
+
+/// Amount in a notation with the given decimal separator
+///
+/// The integer part may be grouped by thousands with a dot, comma, space or
+/// apostrophe (other than the decimal separator). Negative amounts have a leading
+/// minus or are put between parentheses. The text is converted without rounding.
+pub(crate) fn convert_amount(
+    line_nr: usize,
+    field: &'static str,
+    amount_as_text: &str,
+    decimal_separator: char,
+) -> crate::Result<Amount> {
+    parse_amount(amount_as_text, decimal_separator).map_err(|reason| {
+        crate::Error::RecordConversionFailed {
+            line_nr,
+            field,
+            reason: format!("'{}' {}", amount_as_text, reason),
+        }
+    })
+}
+
+fn parse_amount(amount_as_text: &str, decimal_separator: char) -> Result<Amount, &'static str> {
+    let text = amount_as_text.trim();
+    let (between_parentheses, text) = match text.strip_prefix('(') {
+        Some(inner) => match inner.strip_suffix(')') {
+            Some(inner) => (true, inner.trim()),
+            None => return Err("has an unbalanced parenthesis"),
+        },
+        None => (false, text),
+    };
+
+    let (negative, unsigned) = match text.chars().next() {
+        Some(sign @ ('-' | '\u{2212}')) => (true, &text[sign.len_utf8()..]),
+        Some('+') => (false, &text[1..]),
+        Some(_) => (false, text),
+        None => return Err("is empty"),
+    };
+    if negative && between_parentheses {
+        return Err("is negated twice");
+    }
+
+    let (integer_part, fraction) = match unsigned.split_once(decimal_separator) {
+        Some((integer_part, fraction)) => (integer_part, Some(fraction)),
+        None => (unsigned, None),
+    };
+
+    let integer_digits = integer_digits(integer_part, decimal_separator)?;
+    let fraction_digits = match fraction {
+        Some("") => return Err("has no digits after the decimal mark"),
+        Some(fraction) if !fraction.chars().all(|c| c.is_ascii_digit()) => {
+            return Err("is not a valid amount")
+        }
+        Some(fraction) => fraction,
+        None => "0",
+    };
+    if integer_digits.is_empty() && fraction.is_none() {
+        return Err("has no digits");
+    }
+
+    let literal = format!(
+        "{}{}.{}",
+        if negative || between_parentheses {
+            "-"
+        } else {
+            ""
+        },
+        if integer_digits.is_empty() {
+            "0"
+        } else {
+            &integer_digits
+        },
+        fraction_digits
+    );
+    Decimal::from_str_exact(&literal).map_err(|_| "is out of range")
+}
+
+/// The digits of the integer part, after checking the grouping by thousands
+fn integer_digits(integer_part: &str, decimal_separator: char) -> Result<String, &'static str> {
+    const THOUSANDS_SEPARATORS: [char; 5] = ['.', ',', ' ', '\'', '\u{a0}'];
+
+    let separators: Vec<char> = integer_part
+        .chars()
+        .filter(|c| THOUSANDS_SEPARATORS.contains(c))
+        .collect();
+    if separators.is_empty() {
+        return if integer_part.chars().all(|c| c.is_ascii_digit()) {
+            Ok(integer_part.to_string())
+        } else {
+            Err("is not a valid amount")
+        };
+    }
+
+    let separator = separators[0];
+    if separator == decimal_separator || separators.iter().any(|c| *c != separator) {
+        return Err("has misplaced thousands separators");
+    }
+
+    let groups: Vec<&str> = integer_part.split(separator).collect();
+    if !groups
+        .iter()
+        .all(|group| group.chars().all(|c| c.is_ascii_digit()))
+    {
+        return Err("is not a valid amount");
+    }
+    if !(1..=3).contains(&groups[0].len()) || groups[1..].iter().any(|group| group.len() != 3) {
+        return Err("has misplaced thousands separators");
+    }
+
+    Ok(groups.concat())
+}
+
+/// Hex encoded SHA-256 of the fields that identify a bank transaction
+pub(crate) fn transaction_id(hash_base: &str) -> String {
+    let hash = Sha256::digest(hash_base.as_bytes());
+    base16ct::lower::encode_string(&hash)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn amount(text: &str, decimal_separator: char) -> Result<Amount, &'static str> {
+        parse_amount(text, decimal_separator)
+    }
+
+    #[test]
+    fn decimal_comma_and_dot() {
+        assert_eq!(amount("12,50", ','), Ok(Decimal::new(1250, 2)));
+        assert_eq!(amount("12.50", '.'), Ok(Decimal::new(1250, 2)));
+        assert_eq!(amount(",5", ','), Ok(Decimal::new(5, 1)));
+        assert_eq!(amount("7", '.'), Ok(Decimal::new(7, 0)));
+        assert_eq!(amount("0.123456", '.'), Ok(Decimal::new(123456, 6)));
+        assert!(amount("12,", ',').is_err());
+        assert!(amount("12,5a", ',').is_err());
+    }
+
+    #[test]
+    fn thousands_separators() {
+        assert_eq!(amount("1.234.567,89", ','), Ok(Decimal::new(123456789, 2)));
+        assert_eq!(amount("1,234,567.89", '.'), Ok(Decimal::new(123456789, 2)));
+        assert_eq!(amount("1 234,50", ','), Ok(Decimal::new(123450, 2)));
+        assert_eq!(amount("1'234.50", '.'), Ok(Decimal::new(123450, 2)));
+        assert_eq!(amount("1\u{a0}234,50", ','), Ok(Decimal::new(123450, 2)));
+        assert!(amount("12.34,50", ',').is_err());
+        assert!(amount("1.234 567,00", ',').is_err());
+        assert!(amount("1,234,50", ',').is_err());
+        assert!(amount("1234.567.8", '.').is_err());
+    }
+
+    #[test]
+    fn signs() {
+        assert_eq!(amount("-12,50", ','), Ok(Decimal::new(-1250, 2)));
+        assert_eq!(amount("\u{2212}12,50", ','), Ok(Decimal::new(-1250, 2)));
+        assert_eq!(amount("+12,50", ','), Ok(Decimal::new(1250, 2)));
+        assert_eq!(amount("(1.000,00)", ','), Ok(Decimal::new(-100000, 2)));
+        assert_eq!(amount("  -3.00 ", '.'), Ok(Decimal::new(-300, 2)));
+        assert!(amount("(-12,50)", ',').is_err());
+        assert!(amount("(12,50", ',').is_err());
+        assert!(amount("-", ',').is_err());
+        assert!(amount("", ',').is_err());
+    }
+
+    #[test]
+    fn overflow() {
+        assert_eq!(
+            amount("99999999999999999999999999999999", '.'),
+            Err("is out of range")
+        );
+        assert_eq!(
+            amount("7.922.816.251.426.433.759.354.395.033,5", ','),
+            Ok(Decimal::from_i128_with_scale(
+                79_228_162_514_264_337_593_543_950_335,
+                1
+            ))
+        );
+    }
+}
@@ -35,8 +35,8 @@ pub enum Error {
         source: regex::Error,
     },
 
-    #[error("Conflicting account codes {by_name} vs {by_ref}")]
-    ConflictingAccountCodes { by_name: String, by_ref: String },
+    #[error("Conflicting account codes: {rules}")]
+    ConflictingAccountCodes { rules: String },
 
     #[error("Error in journal file")]
     JournalRepositoryFileError {
@@ -44,6 +44,34 @@ pub enum Error {
         source: crate::JournalRepositoryError,
     },
 
+    #[error("Invalid XML statement")]
+    XmlError {
+        #[from]
+        source: roxmltree::Error,
+    },
+
+    #[error("Statement of {iban} does not balance at {tag} on line {line_nr}: opening {opening} plus movements is {calculated}, closing is {closing}")]
+    StatementBalanceMismatch {
+        iban: String,
+        tag: String,
+        line_nr: usize,
+        opening: rust_decimal::Decimal,
+        closing: rust_decimal::Decimal,
+        calculated: rust_decimal::Decimal,
+    },
+
+    #[error("Invalid content for MT940 tag :{tag}: on line {line_nr}")]
+    Mt940InvalidField { tag: String, line_nr: usize },
+
+    #[error("Unexpected MT940 tag :{tag}: on line {line_nr}")]
+    Mt940UnexpectedTag { tag: String, line_nr: usize },
+
+    #[error("Invalid CSV mapping for bank format '{format_code}': {reason}")]
+    InvalidCsvMapping { format_code: String, reason: String },
+
+    #[error("Column '{0}' not found in CSV file")]
+    CsvColumnNotFound(String),
+
     #[error("Urecognised bank account '{0}'")]
     UnrecognisedBankAccount(String),
 
@@ -53,8 +81,91 @@ pub enum Error {
     #[error("Urecognised account code '{0}'")]
     UnrecognisedAccountCode(String),
 
-    #[error("Skipped record #{line_nr}: conversion of '{field}' failed.")]
-    RecordConversionFailed { line_nr: usize, field: &'static str },
+    #[error("Urecognised parent account '{parent_code}' of account '{account_code}'")]
+    UnrecognisedParentAccount {
+        account_code: String,
+        parent_code: String,
+    },
+
+    #[error("Account '{0}' is its own ancestor")]
+    AccountHierarchyCycle(String),
+
+    #[error("Account '{account_code}' of type {account_type:?} can not be under '{parent_code}' of type {parent_type:?}")]
+    IncompatibleAccountType {
+        account_code: String,
+        account_type: crate::AccountType,
+        parent_code: String,
+        parent_type: crate::AccountType,
+    },
+
+    #[error("Invalid budget for account '{account_code}': {reason}")]
+    InvalidBudget {
+        account_code: String,
+        reason: String,
+    },
+
+    #[error("Urecognised period '{0}'")]
+    UnrecognisedPeriod(String),
+
+    #[error("Urecognised bank format '{0}'")]
+    UnrecognisedBankFormat(String),
+
+    #[error("Urecognised export format '{0}', use ledger, hledger or beancount")]
+    UnrecognisedExportFormat(String),
+
+    #[error("Accounts '{first}' and '{second}' both export as '{name}', rename one of them")]
+    ExportAccountNameCollision {
+        name: String,
+        first: String,
+        second: String,
+    },
+
+    #[error("Journal entry '{id}' does not balance: the postings sum up to {balance}")]
+    UnbalancedJournalEntry {
+        id: String,
+        balance: rust_decimal::Decimal,
+    },
+
+    #[error("Invalid assignment rule {rule}: {reason}")]
+    InvalidAssignmentRule { rule: String, reason: String },
+
+    #[error("Invalid split: {0}")]
+    InvalidSplit(String),
+
+    #[error("Urecognised bank transaction '{0}'")]
+    UnrecognisedBankTransaction(String),
+
+    #[error("Bank transaction '{0}' is not assigned to an account")]
+    BankTransactionNotAssigned(String),
+
+    #[error("Bank transaction '{0}' is already assigned to an account")]
+    BankTransactionAlreadyAssigned(String),
+
+    #[error("Urecognised import batch '{0}'")]
+    UnrecognisedImportBatch(String),
+
+    #[error("Import batch '{batch_id}' can not be {action}, it is {status:?}")]
+    ImportBatchNotAllowed {
+        batch_id: String,
+        action: &'static str,
+        status: crate::ImportBatchStatus,
+    },
+
+    #[error("Import batch '{batch_id}' can not be rolled back before '{dependent_batch_id}', which was committed later with the same transactions")]
+    ImportBatchHasDependents {
+        batch_id: String,
+        dependent_batch_id: String,
+    },
+
+    #[error("Could not detect the bank format of '{0}'")]
+    UndetectedBankFormat(String),
+
+    #[error("Skipped record #{line_nr}: conversion of '{field}' failed: {reason}.")]
+    RecordConversionFailed {
+        line_nr: usize,
+        field: &'static str,
+        reason: String,
+    },
 
     #[error("Currency {foreign} must be converted to {ledger}")]
     CurrencyMustBeExchanged { ledger: String, foreign: String },
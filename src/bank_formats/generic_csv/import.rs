@@ -0,0 +1,349 @@
+use std::io::Read;
+
+use csv::StringRecord;
+
+use crate::{
+    bank_formats::conversions::{convert_amount, convert_date, transaction_id},
+    ing::DescriptionProperties,
+    BankImporter, CsvColumn, CsvMapping, CsvSignConvention, Error, ImportedTransaction,
+    ImportedTransactions,
+};
+
+/// Builds the text to hash into the transaction id from the line number and the
+/// untrimmed values of the id columns
+pub type IdHashBase = fn(usize, &[&str]) -> crate::Result<String>;
+
+/// Importer for any CSV export that can be described by a `CsvMapping`
+pub struct CsvImporter {
+    mapping: CsvMapping,
+    id_hash_base: Option<IdHashBase>,
+}
+
+/// The mapped columns as positions in the record
+struct ColumnPositions {
+    account: usize,
+    date: usize,
+    amount: usize,
+    debit_credit: Option<usize>,
+    balance_after: Option<usize>,
+    counter_iban: Option<usize>,
+    name: Option<usize>,
+    description: Vec<usize>,
+    info: Option<usize>,
+    contract: Option<usize>,
+    reference: Option<usize>,
+    tag: Option<usize>,
+    id: Vec<usize>,
+}
+
+impl CsvImporter {
+    pub fn try_new(format_code: &str, mapping: CsvMapping) -> crate::Result<Self> {
+        let invalid = |reason: &str| Error::InvalidCsvMapping {
+            format_code: format_code.to_owned(),
+            reason: reason.to_owned(),
+        };
+
+        if !mapping.delimiter.is_ascii() || !mapping.quote.is_ascii() {
+            return Err(invalid("delimiter and quote must be ASCII characters"));
+        }
+        if mapping.decimal_separator != ',' && mapping.decimal_separator != '.' {
+            return Err(invalid("decimal separator must be a comma or a dot"));
+        }
+        if !mapping.has_headers
+            && mapping_columns(&mapping).any(|column| matches!(column, CsvColumn::Name(_)))
+        {
+            return Err(invalid(
+                "columns can only be named when the file has headers",
+            ));
+        }
+
+        Ok(Self {
+            mapping,
+            id_hash_base: None,
+        })
+    }
+
+    /// Hashes the id columns in a format of its own instead of joining them,
+    /// to keep the ids of an importer that predates the mapping
+    pub fn with_id_hash_base(mut self, id_hash_base: IdHashBase) -> Self {
+        self.id_hash_base = Some(id_hash_base);
+        self
+    }
+
+    pub fn mapping(&self) -> &CsvMapping {
+        &self.mapping
+    }
+
+    fn csv_reader<R: Read>(&self, rdr: R) -> csv::Reader<R> {
+        csv::ReaderBuilder::new()
+            .has_headers(self.mapping.has_headers)
+            .delimiter(self.mapping.delimiter as u8)
+            .quote(self.mapping.quote as u8)
+            .flexible(true)
+            .from_reader(rdr)
+    }
+}
+
+fn mapping_columns(mapping: &CsvMapping) -> impl Iterator<Item = &CsvColumn> {
+    let columns = &mapping.columns;
+    let debit_credit = match &mapping.sign {
+        CsvSignConvention::Signed => None,
+        CsvSignConvention::DebitCreditColumn { column, .. } => Some(column),
+    };
+
+    [&columns.account, &columns.date, &columns.amount]
+        .into_iter()
+        .chain(debit_credit)
+        .chain(columns.balance_after.iter())
+        .chain(columns.counter_iban.iter())
+        .chain(columns.name.iter())
+        .chain(columns.description.iter())
+        .chain(columns.info.iter())
+        .chain(columns.contract.iter())
+        .chain(columns.reference.iter())
+        .chain(columns.tag.iter())
+        .chain(columns.id.iter())
+}
+
+fn position(headers: Option<&StringRecord>, column: &CsvColumn) -> Option<usize> {
+    match column {
+        CsvColumn::Index(index) => Some(*index),
+        CsvColumn::Name(name) => headers.and_then(|headers| {
+            headers
+                .iter()
+                .position(|header| header.trim().eq_ignore_ascii_case(name.trim()))
+        }),
+    }
+}
+
+impl ColumnPositions {
+    fn resolve(mapping: &CsvMapping, headers: Option<&StringRecord>) -> crate::Result<Self> {
+        let required = |column: &CsvColumn| {
+            position(headers, column).ok_or_else(|| Error::CsvColumnNotFound(column.to_string()))
+        };
+        // Optional columns may be missing from older exports
+        let optional = |column: &Option<CsvColumn>| {
+            column.as_ref().and_then(|column| position(headers, column))
+        };
+        let columns = &mapping.columns;
+
+        Ok(Self {
+            account: required(&columns.account)?,
+            date: required(&columns.date)?,
+            amount: required(&columns.amount)?,
+            debit_credit: match &mapping.sign {
+                CsvSignConvention::Signed => None,
+                CsvSignConvention::DebitCreditColumn { column, .. } => Some(required(column)?),
+            },
+            balance_after: optional(&columns.balance_after),
+            counter_iban: optional(&columns.counter_iban),
+            name: optional(&columns.name),
+            description: columns
+                .description
+                .iter()
+                .filter_map(|column| position(headers, column))
+                .collect(),
+            info: optional(&columns.info),
+            contract: optional(&columns.contract),
+            reference: optional(&columns.reference),
+            tag: optional(&columns.tag),
+            id: columns
+                .id
+                .iter()
+                .map(required)
+                .collect::<crate::Result<Vec<usize>>>()?,
+        })
+    }
+}
+
+impl BankImporter for CsvImporter {
+    fn read<'r>(&self, rdr: Box<dyn Read + 'r>) -> ImportedTransactions<'r> {
+        let mut csv_reader = self.csv_reader(rdr);
+
+        let headers = if self.mapping.has_headers {
+            match csv_reader.headers() {
+                Ok(headers) => Some(headers.clone()),
+                Err(e) => return Box::new(std::iter::once(Err(Error::from(e)))),
+            }
+        } else {
+            None
+        };
+
+        let positions = match ColumnPositions::resolve(&self.mapping, headers.as_ref()) {
+            Ok(positions) => positions,
+            Err(e) => return Box::new(std::iter::once(Err(e))),
+        };
+        let first_line_nr = if self.mapping.has_headers { 2 } else { 1 };
+        let mapping = self.mapping.clone();
+        let id_hash_base = self.id_hash_base;
+
+        Box::new(
+            csv_reader
+                .into_records()
+                .enumerate()
+                .map(move |(record_nr, record)| {
+                    convert_record(
+                        record_nr + first_line_nr,
+                        &mapping,
+                        id_hash_base,
+                        &positions,
+                        record?,
+                    )
+                }),
+        )
+    }
+
+    fn confidence(&self, sample: &str) -> f32 {
+        let mut csv_reader = self.csv_reader(sample.as_bytes());
+        let headers = if self.mapping.has_headers {
+            match csv_reader.headers() {
+                Ok(headers) => Some(headers.clone()),
+                Err(_) => return 0.0,
+            }
+        } else {
+            None
+        };
+
+        let positions = match ColumnPositions::resolve(&self.mapping, headers.as_ref()) {
+            Ok(positions) => positions,
+            Err(_) => return 0.0,
+        };
+
+        let named: Vec<&CsvColumn> = mapping_columns(&self.mapping)
+            .filter(|column| matches!(column, CsvColumn::Name(_)))
+            .collect();
+        let header_score = if named.is_empty() {
+            0.6
+        } else {
+            let found = named
+                .iter()
+                .filter(|column| position(headers.as_ref(), column).is_some())
+                .count();
+            found as f32 / named.len() as f32
+        };
+
+        let first_record_score = match csv_reader.records().next() {
+            Some(Ok(record)) => {
+                match convert_record(1, &self.mapping, self.id_hash_base, &positions, record) {
+                    Ok(_) => 1.0,
+                    Err(_) => 0.5,
+                }
+            }
+            _ => 0.8,
+        };
+
+        0.95 * header_score * first_record_score
+    }
+}
+
+fn convert_record(
+    line_nr: usize,
+    mapping: &CsvMapping,
+    id_hash_base: Option<IdHashBase>,
+    positions: &ColumnPositions,
+    record: StringRecord,
+) -> crate::Result<ImportedTransaction> {
+    let field = |position: usize| record.get(position).map(str::trim).unwrap_or_default();
+    let optional_field =
+        |position: Option<usize>| position.map(field).filter(|value| !value.is_empty());
+
+    let date = convert_date(line_nr, "date", field(positions.date), &mapping.date_format)?;
+    let amount = convert_amount(
+        line_nr,
+        "amount",
+        field(positions.amount),
+        mapping.decimal_separator,
+    )?;
+    let amount = match &mapping.sign {
+        CsvSignConvention::Signed => amount,
+        CsvSignConvention::DebitCreditColumn { debit, credit, .. } => {
+            let token = optional_field(positions.debit_credit).unwrap_or_default();
+            if debit.iter().any(|debit| debit.eq_ignore_ascii_case(token)) {
+                -amount.abs()
+            } else if credit
+                .iter()
+                .any(|credit| credit.eq_ignore_ascii_case(token))
+            {
+                amount.abs()
+            } else {
+                return Err(Error::RecordConversionFailed {
+                    line_nr,
+                    field: "debit_credit",
+                    reason: format!("'{}' is neither debit nor credit", token),
+                });
+            }
+        }
+    };
+
+    let balance_after = match optional_field(positions.balance_after) {
+        Some(balance) => Some(convert_amount(
+            line_nr,
+            "balance",
+            balance,
+            mapping.decimal_separator,
+        )?),
+        None => None,
+    };
+
+    let mut props = match optional_field(positions.info) {
+        Some(info) => DescriptionProperties::from(info),
+        None => DescriptionProperties::default(),
+    };
+    let description = positions
+        .description
+        .iter()
+        .filter_map(|position| optional_field(Some(*position)))
+        .collect::<Vec<&str>>()
+        .join(" ");
+    if !description.is_empty() {
+        let description = match props.description() {
+            Some(existing) => format!("{} {}", description, existing),
+            None => description,
+        };
+        props.define_description(description);
+    }
+    if let Some(name) = optional_field(positions.name) {
+        props.define_name(name);
+    }
+    if let Some(contract) = optional_field(positions.contract) {
+        props.define_contract(contract.to_owned());
+    }
+    if let Some(reference) = optional_field(positions.reference) {
+        props.define_reference(reference.to_owned());
+    }
+    if let Some(tag) = optional_field(positions.tag) {
+        props.define_tag(tag.to_owned());
+    }
+
+    let hash_base = if let Some(id_hash_base) = id_hash_base {
+        let values: Vec<&str> = positions
+            .id
+            .iter()
+            .map(|position| record.get(*position).unwrap_or_default())
+            .collect();
+        id_hash_base(line_nr, &values)?
+    } else if positions.id.is_empty() {
+        format!("|{}|", record.iter().collect::<Vec<&str>>().join("|"))
+    } else {
+        format!(
+            "|{}|",
+            positions
+                .id
+                .iter()
+                .map(|position| field(*position))
+                .collect::<Vec<&str>>()
+                .join("|")
+        )
+    };
+
+    Ok(ImportedTransaction {
+        id: transaction_id(&hash_base),
+        iban: field(positions.account).to_owned(),
+        contra_iban: optional_field(positions.counter_iban).map(str::to_owned),
+        date,
+        balance_before: balance_after.map(|balance_after| balance_after - amount),
+        amount,
+        balance_after,
+        properties: props.properties,
+    })
+}
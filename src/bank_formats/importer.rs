@@ -0,0 +1,70 @@
+use std::{collections::HashMap, io::Read};
+
+use chrono::NaiveDate;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    AccountsRepository, BankTransaction, CostCentersRepository, RelationsRepository,
+    TransactionAssigner,
+};
+
+use super::ing::DescriptionProperties;
+
+pub type Amount = Decimal;
+
+/// A single record from a bank export, before any account has been assigned to it
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ImportedTransaction {
+    pub id: String,
+    pub iban: String,
+    pub contra_iban: Option<String>,
+    pub date: NaiveDate,
+    pub balance_before: Option<Amount>,
+    pub amount: Amount,
+    pub balance_after: Option<Amount>,
+    pub properties: HashMap<String, String>,
+}
+
+pub type ImportedTransactions<'r> =
+    Box<dyn Iterator<Item = crate::Result<ImportedTransaction>> + 'r>;
+
+pub type AssignedTransactions<'r> = Box<dyn Iterator<Item = crate::Result<BankTransaction>> + 'r>;
+
+impl ImportedTransaction {
+    pub fn name(&self) -> Option<String> {
+        self.properties.get(DescriptionProperties::NAME).cloned()
+    }
+}
+
+/// Reads the export file of a specific bank
+///
+/// Implementations only need to convert the records of the file; assigning
+/// accounts, cost centers and relations is shared by all formats.
+pub trait BankImporter: Send + Sync {
+    fn read<'r>(&self, rdr: Box<dyn Read + 'r>) -> ImportedTransactions<'r>;
+
+    /// How likely it is (0.0 - 1.0) that a file starting with `sample` is in this format
+    fn confidence(&self, _sample: &str) -> f32 {
+        0.0
+    }
+
+    fn transactions<'r>(
+        &self,
+        rdr: Box<dyn Read + 'r>,
+        accounts_repository: &'r dyn AccountsRepository,
+        cost_center_repository: &'r dyn CostCentersRepository,
+        relations_repository: &'r dyn RelationsRepository,
+    ) -> AssignedTransactions<'r> {
+        let assigner = TransactionAssigner::new(
+            accounts_repository,
+            cost_center_repository,
+            relations_repository,
+        );
+
+        Box::new(
+            self.read(rdr)
+                .map(move |result| result.and_then(|imported| assigner.assign(imported))),
+        )
+    }
+}
@@ -2,6 +2,7 @@ use std::{collections::HashMap, fmt::Display};
 
 use regex::Regex;
 
+#[derive(Default, Debug)]
 pub struct DescriptionProperties {
     pub properties: HashMap<String, String>,
 }
@@ -11,6 +12,8 @@ impl DescriptionProperties {
     pub const NAME: &'static str = "Naam";
     pub const TAG: &'static str = "Tag";
     pub const CONTRACT: &'static str = "Machtiging ID";
+    pub const REFERENCE: &'static str = "Kenmerk";
+    pub const CREDITOR_ID: &'static str = "Incassant ID";
 
     pub fn define_name(&mut self, val: &str) {
         let adjusted = if val.starts_with("CCV") {
@@ -35,6 +38,18 @@ impl DescriptionProperties {
         self.properties.insert(Self::DESCRIPTION.to_owned(), val);
     }
 
+    pub fn define_contract(&mut self, val: String) {
+        self.properties.insert(Self::CONTRACT.to_owned(), val);
+    }
+
+    pub fn define_reference(&mut self, val: String) {
+        self.properties.insert(Self::REFERENCE.to_owned(), val);
+    }
+
+    pub fn define_creditor_id(&mut self, val: String) {
+        self.properties.insert(Self::CREDITOR_ID.to_owned(), val);
+    }
+
     pub fn description(&self) -> Option<&String> {
         self.properties.get(Self::DESCRIPTION)
     }
@@ -1,4 +1,27 @@
+pub mod abn_amro;
+pub mod camt053;
+pub mod generic_csv;
 pub mod ing;
+pub mod mt940;
+pub mod ofx;
+pub mod rabobank;
+
+mod conversions;
 
 mod error;
 pub use error::*;
+
+mod importer;
+pub use importer::*;
+
+mod assignment;
+pub use assignment::*;
+
+mod bank_importers;
+pub use bank_importers::*;
+
+mod balance_continuity;
+pub use balance_continuity::*;
+
+mod sepa_description;
+pub use sepa_description::*;
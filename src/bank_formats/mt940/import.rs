@@ -0,0 +1,405 @@
+use std::io::Read;
+
+use chrono::{Datelike, NaiveDate};
+use regex::Regex;
+
+use crate::{
+    bank_formats::conversions::{convert_amount, transaction_id},
+    ing::DescriptionProperties,
+    Amount, BankImporter, Error, ImportedTransaction, ImportedTransactions, SepaDescription,
+};
+
+/// Importer for SWIFT MT940 customer statements
+#[derive(Default)]
+pub struct Mt940Importer;
+
+impl BankImporter for Mt940Importer {
+    fn read<'r>(&self, mut rdr: Box<dyn Read + 'r>) -> ImportedTransactions<'r> {
+        let mut bytes = vec![];
+        if let Err(e) = rdr.read_to_end(&mut bytes) {
+            return Box::new(std::iter::once(Err(Error::from(e))));
+        }
+
+        let fields = split_fields(String::from_utf8_lossy(&bytes).as_ref());
+        Box::new(Mt940Statements::default().read(fields).into_iter())
+    }
+
+    fn confidence(&self, sample: &str) -> f32 {
+        let tags = [":20:", ":25:", ":28C:", ":60F:", ":61:", ":86:"];
+        let found = tags
+            .iter()
+            .filter(|tag| sample.lines().any(|line| line.starts_with(*tag)))
+            .count();
+
+        if found >= 3 {
+            0.5 + 0.075 * found as f32
+        } else {
+            0.0
+        }
+    }
+}
+
+/// A tag with its (possibly multi line) content
+struct Field {
+    tag: String,
+    line_nr: usize,
+    lines: Vec<String>,
+}
+
+impl Field {
+    fn invalid(&self) -> Error {
+        Error::Mt940InvalidField {
+            tag: self.tag.clone(),
+            line_nr: self.line_nr,
+        }
+    }
+
+    fn unexpected(&self) -> Error {
+        Error::Mt940UnexpectedTag {
+            tag: self.tag.clone(),
+            line_nr: self.line_nr,
+        }
+    }
+
+    fn content(&self) -> String {
+        // Structured :86: content is wrapped at a fixed width, free text on word boundaries
+        let separator = if self.lines[0].starts_with('/') {
+            ""
+        } else {
+            " "
+        };
+        self.lines.join(separator)
+    }
+}
+
+fn split_fields(statement: &str) -> Vec<Field> {
+    let tag = Regex::new(r"^:(\d{2}[A-Z]?):(.*)$").unwrap();
+    let block_delimiter = Regex::new(r"^(\{\d:.*|-\}?|\})$").unwrap();
+    let mut fields: Vec<Field> = vec![];
+
+    for (index, line) in statement.lines().enumerate() {
+        let line = line.trim_end();
+        if let Some(captures) = tag.captures(line) {
+            fields.push(Field {
+                tag: captures[1].to_owned(),
+                line_nr: index + 1,
+                lines: vec![captures[2].to_owned()],
+            });
+        } else if block_delimiter.is_match(line) || line.is_empty() {
+            // SWIFT block headers and trailers
+            continue;
+        } else if let Some(field) = fields.last_mut() {
+            field.lines.push(line.to_owned());
+        }
+    }
+
+    fields
+}
+
+fn convert_yymmdd(field: &Field, yymmdd: &str) -> crate::Result<NaiveDate> {
+    NaiveDate::parse_from_str(&format!("20{}", yymmdd), "%Y%m%d").map_err(|_| field.invalid())
+}
+
+/// MT940 amounts always have a decimal comma, the decimals themselves are optional
+fn signed_amount(field: &Field, amount: &str, debit_or_credit: &str) -> crate::Result<Amount> {
+    let amount = amount.strip_suffix(',').unwrap_or(amount);
+    let amount = convert_amount(field.line_nr, "amount", amount, ',')?;
+
+    Ok(match debit_or_credit {
+        "D" | "RC" => -amount,
+        _ => amount,
+    })
+}
+
+/// Opening and closing balances, like `C220101EUR1000,00`
+fn convert_balance(field: &Field) -> crate::Result<Amount> {
+    let balance = Regex::new(r"^(C|D)(\d{6})([A-Z]{3})(\d+,\d*)$").unwrap();
+    let captures = balance
+        .captures(field.lines[0].trim())
+        .ok_or_else(|| field.invalid())?;
+
+    signed_amount(field, &captures[4], &captures[1])
+}
+
+struct StatementLine {
+    line_nr: usize,
+    date: NaiveDate,
+    amount: Amount,
+    details: String,
+    information: Option<String>,
+}
+
+#[derive(Default)]
+struct Mt940Statements {
+    iban: Option<String>,
+    opening: Option<Amount>,
+    lines: Vec<StatementLine>,
+    results: Vec<crate::Result<ImportedTransaction>>,
+}
+
+impl Mt940Statements {
+    fn read(mut self, fields: Vec<Field>) -> Vec<crate::Result<ImportedTransaction>> {
+        for field in fields.iter() {
+            if let Err(e) = self.read_field(field) {
+                self.results.push(Err(e));
+            }
+        }
+
+        if !self.lines.is_empty() {
+            // Statement without closing balance
+            self.flush(None);
+        }
+
+        self.results
+    }
+
+    fn read_field(&mut self, field: &Field) -> crate::Result<()> {
+        match field.tag.as_str() {
+            "20" => {
+                if !self.lines.is_empty() {
+                    // Previous statement without closing balance
+                    self.flush(None);
+                }
+                self.iban = None;
+                self.opening = None;
+                self.lines.clear();
+            }
+            "25" => {
+                // Either the IBAN itself or BIC/account, optionally followed by a currency
+                let account = field.lines[0].trim();
+                let account = account.rsplit('/').next().unwrap_or(account);
+                let account = account_without_currency(account);
+                if account.is_empty() {
+                    return Err(field.invalid());
+                }
+                self.iban = Some(account.to_owned());
+            }
+            "60F" | "60M" => self.opening = Some(convert_balance(field)?),
+            "61" => self.lines.push(convert_statement_line(field)?),
+            "86" => match self.lines.last_mut() {
+                Some(line) if line.information.is_none() => {
+                    line.information = Some(field.content())
+                }
+                Some(_) => return Err(field.unexpected()),
+                // Information about the statement as a whole
+                None => {}
+            },
+            "62F" | "62M" => {
+                let closing = convert_balance(field)?;
+                self.flush(Some((field, closing)));
+            }
+            _ => {}
+        }
+
+        Ok(())
+    }
+
+    fn flush(&mut self, closing: Option<(&Field, Amount)>) {
+        let lines: Vec<StatementLine> = self.lines.drain(..).collect();
+        let iban = match &self.iban {
+            Some(iban) => iban.clone(),
+            None => {
+                if let Some(first) = lines.first() {
+                    self.results.push(Err(Error::Mt940UnexpectedTag {
+                        tag: "61".to_owned(),
+                        line_nr: first.line_nr,
+                    }));
+                }
+                return;
+            }
+        };
+
+        let mut running_balance = self.opening;
+        let mut total = Amount::ZERO;
+        for line in lines {
+            total += line.amount;
+            let balance_before = running_balance;
+            running_balance = running_balance.map(|balance| balance + line.amount);
+            self.results.push(Ok(convert_line(
+                &iban,
+                line,
+                balance_before,
+                running_balance,
+            )));
+        }
+
+        if let (Some(opening), Some((field, closing))) = (self.opening, closing) {
+            if opening + total != closing {
+                self.results.push(Err(Error::StatementBalanceMismatch {
+                    iban,
+                    tag: field.tag.clone(),
+                    line_nr: field.line_nr,
+                    opening,
+                    closing,
+                    calculated: opening + total,
+                }));
+            }
+        }
+
+        // The next statement (page) starts where this one ended
+        self.opening = closing.map(|(_, closing)| closing);
+    }
+}
+
+/// The account of a `:25:` field; the currency is only stripped when the account
+/// is not a valid IBAN by itself, as IBANs of some countries end with letters
+fn account_without_currency(account: &str) -> &str {
+    if is_valid_iban(account) {
+        return account;
+    }
+
+    let currency_suffix = Regex::new(r"^(.*?)([A-Z]{3})?$").unwrap();
+    currency_suffix
+        .captures(account)
+        .and_then(|captures| captures.get(1))
+        .map_or(account, |without_currency| without_currency.as_str())
+}
+
+/// Country code, check digits and account, with the ISO 13616 modulo 97 check
+fn is_valid_iban(iban: &str) -> bool {
+    if iban.len() < 15
+        || !iban.chars().all(|c| c.is_ascii_alphanumeric())
+        || !iban[..2].chars().all(|c| c.is_ascii_uppercase())
+    {
+        return false;
+    }
+
+    let rearranged = format!("{}{}", &iban[4..], &iban[..4]);
+    let remainder =
+        rearranged
+            .chars()
+            .filter_map(|c| c.to_digit(36))
+            .fold(0u32, |remainder, value| {
+                let factor = if value < 10 { 10 } else { 100 };
+                (remainder * factor + value) % 97
+            });
+    remainder == 1
+}
+
+/// `:61:` value date, optional entry date, debit/credit mark, amount, type and references
+fn convert_statement_line(field: &Field) -> crate::Result<StatementLine> {
+    let statement_line =
+        Regex::new(r"^(\d{6})(\d{4})?(RC|RD|C|D)[A-Z]?(\d+,\d*)([A-Z]\w{3})(.*)$").unwrap();
+    let captures = statement_line
+        .captures(field.lines[0].trim())
+        .ok_or_else(|| field.invalid())?;
+
+    let value_date = convert_yymmdd(field, &captures[1])?;
+    let date = match captures.get(2) {
+        Some(entry_date) => {
+            let entry_date = entry_date.as_str();
+            let month: u32 = entry_date[..2].parse().map_err(|_| field.invalid())?;
+            let year = match (value_date.month(), month) {
+                (12, 1) => value_date.year() + 1,
+                (1, 12) => value_date.year() - 1,
+                _ => value_date.year(),
+            };
+            NaiveDate::parse_from_str(&format!("{}{}", year, entry_date), "%Y%m%d")
+                .map_err(|_| field.invalid())?
+        }
+        None => value_date,
+    };
+    let amount = signed_amount(field, &captures[4], &captures[3])?;
+
+    Ok(StatementLine {
+        line_nr: field.line_nr,
+        date,
+        amount,
+        details: field.lines.join("|"),
+        information: None,
+    })
+}
+
+fn convert_line(
+    iban: &str,
+    line: StatementLine,
+    balance_before: Option<Amount>,
+    balance_after: Option<Amount>,
+) -> ImportedTransaction {
+    let information = line.information.unwrap_or_default();
+    let (contra_iban, properties) = if SepaDescription::is_sepa(&information) {
+        let sepa = SepaDescription::from(information.as_str());
+        (sepa.counter_iban, sepa.properties)
+    } else {
+        let mut properties = DescriptionProperties::default();
+        if !information.trim().is_empty() {
+            properties.define_description(information.trim().to_owned());
+        }
+        (None, properties)
+    };
+
+    let hash_base = format!(
+        "|{iban}|{date}|{amount}|{details}|{information}|",
+        date = line.date,
+        amount = line.amount,
+        details = line.details,
+    );
+
+    ImportedTransaction {
+        id: transaction_id(&hash_base),
+        iban: iban.to_owned(),
+        contra_iban,
+        date: line.date,
+        balance_before,
+        amount: line.amount,
+        balance_after,
+        properties: properties.properties,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn import(content: &str) -> Vec<crate::Result<ImportedTransaction>> {
+        Mt940Importer.read(Box::new(content.as_bytes())).collect()
+    }
+
+    #[test]
+    fn keeps_statement_without_closing_balance() {
+        let transactions = import(
+            ":20:S1\n:25:NL91ABNA0417164300\n:60F:C220101EUR1000,00\n\
+             :61:2201030103D12,50NTRFNONREF\n:86:First\n\
+             :20:S2\n:25:NL91ABNA0417164300\n:60F:C220201EUR987,50\n\
+             :61:2202030203D2,50NTRFNONREF\n:86:Second\n:62F:C220228EUR985,00\n-}\n",
+        );
+
+        assert_eq!(transactions.len(), 2);
+        assert!(transactions.iter().all(Result::is_ok));
+    }
+
+    #[test]
+    fn keeps_continuation_lines_starting_with_a_dash() {
+        let fields = split_fields("{1:F01}{2:I940}{4:\n:20:S1\n:86:Part one\n-part two\n-}\n");
+
+        assert_eq!(fields.len(), 2);
+        assert_eq!(fields[1].lines, vec!["Part one", "-part two"]);
+    }
+
+    #[test]
+    fn strips_only_a_currency_suffix() {
+        assert_eq!(
+            account_without_currency("NL91ABNA0417164300EUR"),
+            "NL91ABNA0417164300"
+        );
+        assert_eq!(
+            account_without_currency("MT84MALT011000012345MTLCAST001S"),
+            "MT84MALT011000012345MTLCAST001S"
+        );
+        assert_eq!(account_without_currency("123456789EUR"), "123456789");
+        assert_eq!(account_without_currency("123456789"), "123456789");
+    }
+
+    #[test]
+    fn balance_mismatch_names_the_closing_balance() {
+        let transactions = import(
+            ":20:S1\n:25:NL91ABNA0417164300\n:60F:C220101EUR1000,00\n\
+             :61:2201030103D12,50NTRFNONREF\n:86:First\n:62F:C220131EUR990,00\n",
+        );
+
+        assert!(matches!(
+            transactions.last(),
+            Some(Err(Error::StatementBalanceMismatch { tag, line_nr: 6, .. })) if tag == "62F"
+        ));
+    }
+}
@@ -0,0 +1,207 @@
+use std::{collections::HashMap, io::Read};
+
+use regex::Regex;
+
+use crate::{
+    bank_formats::conversions::{convert_amount, convert_date},
+    ing::DescriptionProperties,
+    BankImporter, Error, ImportedTransaction, ImportedTransactions,
+};
+
+/// Importer for Open Financial Exchange (OFX/QFX) statements, both the
+/// SGML based 1.x and the XML based 2.x versions
+#[derive(Default)]
+pub struct OfxImporter;
+
+impl BankImporter for OfxImporter {
+    fn read<'r>(&self, mut rdr: Box<dyn Read + 'r>) -> ImportedTransactions<'r> {
+        let mut bytes = vec![];
+        if let Err(e) = rdr.read_to_end(&mut bytes) {
+            return Box::new(std::iter::once(Err(Error::from(e))));
+        }
+
+        let results = read_statements(String::from_utf8_lossy(&bytes).as_ref());
+        Box::new(results.into_iter())
+    }
+
+    fn confidence(&self, sample: &str) -> f32 {
+        if sample.trim_start().starts_with("OFXHEADER:") || sample.contains("<?OFX ") {
+            0.99
+        } else if sample.to_ascii_uppercase().contains("<OFX>") {
+            0.9
+        } else {
+            0.0
+        }
+    }
+}
+
+/// The elements of one `STMTTRN` aggregate
+struct StatementTransaction {
+    line_nr: usize,
+    account_id: Option<String>,
+    /// The `ACCTID` of the `BANKACCTTO` or `CCACCTTO` of a transfer
+    contra_account_id: Option<String>,
+    elements: HashMap<String, String>,
+}
+
+fn decode_entities(text: &str) -> String {
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&nbsp;", " ")
+        .replace("&amp;", "&")
+}
+
+fn read_statements(ofx: &str) -> Vec<crate::Result<ImportedTransaction>> {
+    // SGML leaf elements have no end tag, so only the text directly after a start tag is used
+    let tag = Regex::new(r"<(/?)([A-Za-z0-9.]+)>([^<]*)").unwrap();
+
+    let mut results = vec![];
+    let mut account_id: Option<String> = None;
+    let mut in_account = false;
+    let mut in_contra_account = false;
+    let mut transaction: Option<StatementTransaction> = None;
+    let mut line_nr = 1;
+    let mut counted_until = 0;
+
+    for captures in tag.captures_iter(ofx) {
+        let is_end_tag = !captures[1].is_empty();
+        let name = captures[2].to_ascii_uppercase();
+        let text = captures[3].trim();
+
+        match (is_end_tag, name.as_str()) {
+            (false, "BANKACCTFROM") | (false, "CCACCTFROM") => in_account = true,
+            (true, "BANKACCTFROM") | (true, "CCACCTFROM") => in_account = false,
+            (false, "BANKACCTTO") | (false, "CCACCTTO") => in_contra_account = true,
+            (true, "BANKACCTTO") | (true, "CCACCTTO") => in_contra_account = false,
+            (false, "ACCTID") if in_account && transaction.is_none() => {
+                account_id = Some(decode_entities(text))
+            }
+            (false, "ACCTID") => {
+                if let Some(transaction) = transaction.as_mut().filter(|_| in_contra_account) {
+                    transaction.contra_account_id = Some(decode_entities(text));
+                }
+            }
+            (false, "STMTTRN") => {
+                let start = captures.get(0).unwrap().start();
+                line_nr += ofx[counted_until..start].matches('\n').count();
+                counted_until = start;
+                transaction = Some(StatementTransaction {
+                    line_nr,
+                    account_id: account_id.clone(),
+                    contra_account_id: None,
+                    elements: HashMap::new(),
+                });
+            }
+            (true, "STMTTRN") => {
+                if let Some(transaction) = transaction.take() {
+                    results.push(convert_transaction(transaction));
+                }
+            }
+            (false, _) if !text.is_empty() => {
+                if let Some(transaction) = transaction.as_mut() {
+                    transaction.elements.insert(name, decode_entities(text));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    results
+}
+
+fn convert_transaction(transaction: StatementTransaction) -> crate::Result<ImportedTransaction> {
+    let line_nr = transaction.line_nr;
+    let elements = transaction.elements;
+    let element = |field: &'static str| {
+        elements.get(field).ok_or(Error::RecordConversionFailed {
+            line_nr,
+            field,
+            reason: "is missing".to_string(),
+        })
+    };
+
+    let account_id = transaction
+        .account_id
+        .ok_or(Error::RecordConversionFailed {
+            line_nr,
+            field: "ACCTID",
+            reason: "is missing".to_string(),
+        })?;
+    let fit_id = element("FITID")?;
+
+    let date_posted = element("DTPOSTED")?;
+    let date = convert_date(
+        line_nr,
+        "DTPOSTED",
+        date_posted.get(..8).unwrap_or(date_posted),
+        "%Y%m%d",
+    )?;
+    let amount = convert_amount(line_nr, "TRNAMT", element("TRNAMT")?, '.')?;
+
+    let mut props = DescriptionProperties::default();
+    if let Some(name) = elements.get("NAME") {
+        props.define_name(name);
+    }
+    if let Some(memo) = elements.get("MEMO") {
+        props.define_description(memo.clone());
+    }
+    if let Some(reference) = elements.get("CHECKNUM").or_else(|| elements.get("REFNUM")) {
+        props.define_reference(reference.clone());
+    }
+
+    Ok(ImportedTransaction {
+        // The financial institution's id is only unique within the account
+        id: format!("{}:{}", account_id, fit_id),
+        iban: account_id,
+        contra_iban: transaction.contra_account_id,
+        date,
+        balance_before: None,
+        amount,
+        balance_after: None,
+        properties: props.properties,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const STATEMENT: &str = "OFXHEADER:100\n<OFX><BANKMSGSRSV1><STMTTRNRS><STMTRS>\n\
+        <BANKACCTFROM><BANKID>ABNA<ACCTID>NL91ABNA0417164300</BANKACCTFROM>\n\
+        <BANKTRANLIST>\n\
+        <STMTTRN><TRNTYPE>DEBIT<DTPOSTED>20220103<TRNAMT>-12.50<FITID>1<NAME>Eneco\n\
+        <BANKACCTTO><BANKID>INGB<ACCTID>NL22INGB0000000002</BANKACCTTO></STMTTRN>\n\
+        <STMTTRN><TRNTYPE>DEBIT<DTPOSTED>20220104<TRNAMT>-3.50<FITID>2<NAME>Bakker\n\
+        <ACCTID>NL00XXXX0000000099</STMTTRN>\n\
+        </BANKTRANLIST></STMTRS></STMTTRNRS></BANKMSGSRSV1></OFX>\n";
+
+    #[test]
+    fn counts_the_lines_of_the_transactions() {
+        // Without FITID both fail, with their line number
+        let line_nrs: Vec<usize> = read_statements(&STATEMENT.replace("<FITID>", "<X>"))
+            .into_iter()
+            .map(|result| match result {
+                Err(Error::RecordConversionFailed { line_nr, .. }) => line_nr,
+                _ => 0,
+            })
+            .collect();
+        assert_eq!(line_nrs, vec![5, 7]);
+    }
+
+    #[test]
+    fn counter_account_only_from_the_transfer_destination() {
+        let transactions: Vec<ImportedTransaction> = read_statements(STATEMENT)
+            .into_iter()
+            .map(Result::unwrap)
+            .collect();
+
+        assert_eq!(transactions[0].iban, "NL91ABNA0417164300");
+        assert_eq!(
+            transactions[0].contra_iban.as_deref(),
+            Some("NL22INGB0000000002")
+        );
+        assert_eq!(transactions[1].contra_iban, None);
+    }
+}
@@ -0,0 +1,150 @@
+use std::io::Read;
+
+use serde::Deserialize;
+
+use crate::{
+    bank_formats::conversions::{convert_amount, convert_date, transaction_id},
+    ing::DescriptionProperties,
+    BankImporter, ImportedTransaction, ImportedTransactions,
+};
+
+#[derive(Deserialize, Debug)]
+struct RabobankImport {
+    #[serde(rename = "IBAN/BBAN")]
+    pub iban: String,
+
+    #[serde(rename = "Volgnr")]
+    pub sequence_nr: String,
+
+    #[serde(rename = "Datum")]
+    pub date: String,
+
+    #[serde(rename = "Bedrag")]
+    pub amount: String,
+
+    #[serde(rename = "Saldo na trn")]
+    pub balance_after_transaction: String,
+
+    #[serde(rename = "Tegenrekening IBAN/BBAN")]
+    pub counter_iban: Option<String>,
+
+    #[serde(rename = "Naam tegenpartij")]
+    pub name: Option<String>,
+
+    #[serde(rename = "Omschrijving-1")]
+    pub description_1: Option<String>,
+
+    #[serde(rename = "Omschrijving-2")]
+    pub description_2: Option<String>,
+
+    #[serde(rename = "Omschrijving-3")]
+    pub description_3: Option<String>,
+
+    #[serde(rename = "Betalingskenmerk")]
+    pub payment_reference: Option<String>,
+
+    #[serde(rename = "Machtigingskenmerk")]
+    pub mandate_reference: Option<String>,
+}
+
+/// Importer for the comma separated transaction export of the Dutch Rabobank
+#[derive(Default)]
+pub struct RabobankImporter;
+
+impl BankImporter for RabobankImporter {
+    fn read<'r>(&self, rdr: Box<dyn Read + 'r>) -> ImportedTransactions<'r> {
+        let csv_reader = csv::ReaderBuilder::new()
+            .has_headers(true)
+            .delimiter(b',')
+            .quote(b'"')
+            .from_reader(rdr);
+
+        Box::new(
+            csv_reader
+                .into_deserialize::<RabobankImport>()
+                .enumerate()
+                .map(|(record_nr, rabobank_import_result)| {
+                    let line_nr = record_nr + 2;
+                    convert_record(line_nr, rabobank_import_result?)
+                }),
+        )
+    }
+
+    fn confidence(&self, sample: &str) -> f32 {
+        let header = sample.lines().next().unwrap_or_default();
+        if header.contains("\"IBAN/BBAN\",") && header.contains("\"Volgnr\"") {
+            0.95
+        } else {
+            0.0
+        }
+    }
+}
+
+fn non_empty(field: Option<String>) -> Option<String> {
+    field.and_then(|val| {
+        let val = val.trim();
+        if val.is_empty() {
+            None
+        } else {
+            Some(val.to_owned())
+        }
+    })
+}
+
+fn convert_record(line_nr: usize, record: RabobankImport) -> crate::Result<ImportedTransaction> {
+    let date = convert_date(line_nr, "date", &record.date, "%Y-%m-%d")?;
+    let amount = convert_amount(line_nr, "amount", &record.amount, ',')?;
+    let balance_after = convert_amount(line_nr, "balance", &record.balance_after_transaction, ',')?;
+
+    let balance_before = balance_after - amount;
+
+    let contra_iban = non_empty(record.counter_iban);
+    let name = non_empty(record.name);
+    let description = [
+        record.description_1,
+        record.description_2,
+        record.description_3,
+    ]
+    .into_iter()
+    .filter_map(non_empty)
+    .collect::<Vec<_>>()
+    .join(" ");
+
+    let hash_base = format!(
+        "|{iban}|{sequence_nr}|{date}|{amount}|{counter_iban}|{balance}|{name} {description}|",
+        iban = record.iban,
+        sequence_nr = record.sequence_nr,
+        date = record.date,
+        amount = record.amount,
+        counter_iban = contra_iban.as_deref().unwrap_or_default(),
+        balance = record.balance_after_transaction,
+        name = name.as_deref().unwrap_or_default(),
+        description = description,
+    );
+    let id = transaction_id(&hash_base);
+
+    let mut props = DescriptionProperties::default();
+    if !description.is_empty() {
+        props.define_description(description);
+    }
+    if let Some(name) = name {
+        props.define_name(&name);
+    }
+    if let Some(payment_reference) = non_empty(record.payment_reference) {
+        props.define_reference(payment_reference);
+    }
+    if let Some(mandate_reference) = non_empty(record.mandate_reference) {
+        props.define_contract(mandate_reference);
+    }
+
+    Ok(ImportedTransaction {
+        id,
+        iban: record.iban,
+        contra_iban,
+        date,
+        balance_before: Some(balance_before),
+        amount,
+        balance_after: Some(balance_after),
+        properties: props.properties,
+    })
+}
@@ -0,0 +1,104 @@
+use regex::Regex;
+
+use crate::ing::DescriptionProperties;
+
+/// A description with slash delimited subfields (`/IBAN/.../NAME/.../REMI/...`),
+/// as used by the SEPA variants of several statement formats
+#[derive(Default, Debug)]
+pub struct SepaDescription {
+    pub counter_iban: Option<String>,
+    pub properties: DescriptionProperties,
+}
+
+/// Keys that may appear in the slash delimited SEPA description
+const SEPA_KEYS: [&str; 15] = [
+    "TRTP", "CSID", "NAME", "MARF", "REMI", "IBAN", "BIC", "EREF", "ORDP", "BENM", "ID", "ADDR",
+    "ISDT", "RTRN", "CNTP",
+];
+
+impl SepaDescription {
+    pub fn is_sepa(description: &str) -> bool {
+        let first_key = Regex::new(format!("^/({})/", SEPA_KEYS.join("|")).as_str()).unwrap();
+        first_key.is_match(description.trim_start())
+    }
+}
+
+impl From<&str> for SepaDescription {
+    fn from(description: &str) -> Self {
+        let keys = Regex::new(format!("/({})/", SEPA_KEYS.join("|")).as_str()).unwrap();
+        let markers: Vec<(usize, usize, &str)> = keys
+            .captures_iter(description)
+            .map(|capture| {
+                let marker = capture.get(0).unwrap();
+                (
+                    marker.start(),
+                    marker.end(),
+                    capture.get(1).unwrap().as_str(),
+                )
+            })
+            .collect();
+
+        let mut result = Self::default();
+        for (index, (_, value_start, key)) in markers.iter().enumerate() {
+            let value_end = match markers.get(index + 1) {
+                Some((next_start, _, _)) => *next_start,
+                None => description.len(),
+            };
+            let value = description[*value_start..value_end]
+                .trim_end_matches('/')
+                .trim();
+            if value.is_empty() {
+                continue;
+            }
+
+            match *key {
+                "IBAN" => result.counter_iban = Some(value.to_owned()),
+                "NAME" => result.properties.define_name(value),
+                "REMI" => result.properties.define_description(
+                    value
+                        .trim_start_matches("USTD")
+                        .trim_start_matches('/')
+                        .to_owned(),
+                ),
+                "MARF" => result.properties.define_contract(value.to_owned()),
+                "CSID" => result.properties.define_creditor_id(value.to_owned()),
+                "EREF" => result.properties.define_reference(value.to_owned()),
+                // Counterparty as account/BIC/name/city
+                "CNTP" => {
+                    let mut parts = value.split('/');
+                    if let Some(iban) = parts.next().filter(|iban| !iban.is_empty()) {
+                        result.counter_iban = Some(iban.to_owned());
+                    }
+                    if let Some(name) = parts.nth(1).filter(|name| !name.is_empty()) {
+                        result.properties.define_name(name);
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keeps_the_creditor_scheme_id() {
+        let description = SepaDescription::from(
+            "/TRTP/SEPA INCASSO/CSID/NL00ZZZ/NAME/Eneco/MARF/MND123/REMI/Energie jan",
+        );
+
+        let properties = &description.properties.properties;
+        assert_eq!(
+            properties.get(DescriptionProperties::CREDITOR_ID),
+            Some(&"NL00ZZZ".to_string())
+        );
+        assert_eq!(
+            properties.get(DescriptionProperties::CONTRACT),
+            Some(&"MND123".to_string())
+        );
+    }
+}
@@ -16,6 +16,7 @@ use super::render_html_template::render_html_template;
 #[derive(Serialize)]
 struct IndexContext {
     accounts: Vec<AccountHibernate>,
+    bank_accounts: Vec<String>,
 }
 
 pub async fn index(
@@ -24,5 +25,10 @@ pub async fn index(
 ) -> impl IntoResponse {
     render_html_template(app, "index", |ledger| IndexContext {
         accounts: ledger.accounts_for_hibernate(),
+        bank_accounts: ledger
+            .bank_accounts()
+            .into_iter()
+            .map(|bank_account| bank_account.iban.clone())
+            .collect(),
     })
 }
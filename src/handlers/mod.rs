@@ -13,3 +13,5 @@ mod render_html_template;
 pub use render_html_template::*;
 
 pub mod transactions;
+
+pub mod reports;
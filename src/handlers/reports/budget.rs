@@ -0,0 +1,59 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::{Extension, Query},
+    response::{Html, IntoResponse, Response},
+    Json,
+};
+use serde::Serialize;
+use tracing::error;
+
+use crate::{
+    handlers::render_html_template, AccountHibernate, BudgetReport, CostCentersRepository,
+    PerfinApp,
+};
+
+use super::ReportQuery;
+
+#[derive(Serialize)]
+struct BudgetContext {
+    report: BudgetReport,
+    query: ReportQuery,
+    cost_centers: Vec<String>,
+    accounts: Vec<AccountHibernate>,
+}
+
+/// Budget against actual amounts per budgeted account, as page or as JSON
+pub async fn budget(
+    Extension(app): Extension<Arc<PerfinApp>>,
+    Query(query): Query<ReportQuery>,
+) -> Response {
+    let report = {
+        let ledger = app.use_ledger();
+        query
+            .period()
+            .and_then(|period| BudgetReport::new(&ledger, period, query.cost_center()))
+    };
+    let report = match report {
+        Ok(report) => report,
+        Err(e) => {
+            error!("Budget report failed: {:?}", e);
+            return Html(format!("Error: {}", e)).into_response();
+        }
+    };
+
+    match query.format() {
+        "json" => Json(report).into_response(),
+        _ => render_html_template(app, "budget", |ledger| BudgetContext {
+            report,
+            query,
+            cost_centers: ledger
+                .cost_centers()
+                .into_iter()
+                .map(str::to_string)
+                .collect(),
+            accounts: ledger.accounts_for_hibernate(),
+        })
+        .into_response(),
+    }
+}
@@ -0,0 +1,76 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::{Extension, Path, Query},
+    http::header,
+    response::{Html, IntoResponse, Response},
+};
+use serde::Deserialize;
+use tracing::error;
+
+use crate::{account_chart, bank_balance_chart, income_expense_chart, PerfinApp};
+
+#[derive(Deserialize, Debug, Default)]
+pub struct ChartQuery {
+    cost_center: Option<String>,
+    /// Any value serves the chart as a file download instead of an image
+    download: Option<String>,
+}
+
+impl ChartQuery {
+    fn cost_center(&self) -> Option<&str> {
+        self.cost_center.as_deref().filter(|code| !code.is_empty())
+    }
+}
+
+fn svg_response(chart: crate::Result<String>, query: &ChartQuery, filename: &str) -> Response {
+    let svg = match chart {
+        Ok(svg) => svg,
+        Err(e) => {
+            error!("Chart failed: {:?}", e);
+            return Html(format!("Error: {}", e)).into_response();
+        }
+    };
+
+    let disposition = match query.download {
+        Some(_) => format!("attachment; filename=\"{}.svg\"", filename),
+        None => "inline".to_string(),
+    };
+    (
+        [
+            (header::CONTENT_TYPE, "image/svg+xml".to_string()),
+            (header::CONTENT_DISPOSITION, disposition),
+        ],
+        svg,
+    )
+        .into_response()
+}
+
+/// Monthly bar chart of an account, optionally for a single cost center
+pub async fn account_chart_svg(
+    Extension(app): Extension<Arc<PerfinApp>>,
+    Path(account_code): Path<String>,
+    Query(query): Query<ChartQuery>,
+) -> Response {
+    let chart = account_chart(&app.use_ledger(), &account_code, query.cost_center());
+    svg_response(chart, &query, &format!("account_{}", account_code))
+}
+
+/// Monthly income and expenses stacked by cost center
+pub async fn income_expense_chart_svg(
+    Extension(app): Extension<Arc<PerfinApp>>,
+    Query(query): Query<ChartQuery>,
+) -> Response {
+    let chart = income_expense_chart(&app.use_ledger());
+    svg_response(chart, &query, "income_expense")
+}
+
+/// Balance of a bank account over the year
+pub async fn bank_balance_chart_svg(
+    Extension(app): Extension<Arc<PerfinApp>>,
+    Path(iban): Path<String>,
+    Query(query): Query<ChartQuery>,
+) -> Response {
+    let chart = bank_balance_chart(&app.use_ledger(), &iban);
+    svg_response(chart, &query, &format!("balance_{}", iban))
+}
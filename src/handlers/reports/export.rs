@@ -0,0 +1,45 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::{Extension, Path},
+    http::header,
+    response::{Html, IntoResponse, Response},
+};
+use tracing::error;
+
+use crate::{export_journal, ExportFormat, PerfinApp};
+
+/// The journal in ledger-cli, hledger or beancount syntax, as download
+pub async fn export(
+    Extension(app): Extension<Arc<PerfinApp>>,
+    Path(format): Path<String>,
+) -> Response {
+    let exported = format.parse::<ExportFormat>().and_then(|format| {
+        let ledger = app.use_ledger();
+        let mut output = vec![];
+        export_journal(&ledger, format, &mut output)?;
+        let file_name = format!("journal_{}.{}", ledger.year(), format.extension());
+        Ok((output, file_name))
+    });
+
+    match exported {
+        Ok((output, file_name)) => (
+            [
+                (
+                    header::CONTENT_TYPE,
+                    "text/plain; charset=utf-8".to_string(),
+                ),
+                (
+                    header::CONTENT_DISPOSITION,
+                    format!("attachment; filename=\"{}\"", file_name),
+                ),
+            ],
+            output,
+        )
+            .into_response(),
+        Err(e) => {
+            error!("Export failed: {:?}", e);
+            Html(format!("Error: {}", e)).into_response()
+        }
+    }
+}
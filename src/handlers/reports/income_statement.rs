@@ -0,0 +1,82 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::{Extension, Query},
+    http::header,
+    response::{Html, IntoResponse, Response},
+    Json,
+};
+use serde::Serialize;
+use tracing::error;
+
+use crate::{
+    handlers::render_html_template, AccountHibernate, CostCentersRepository, IncomeStatements,
+    PerfinApp,
+};
+
+use super::ReportQuery;
+
+#[derive(Serialize)]
+struct IncomeStatementContext {
+    report: IncomeStatements,
+    /// Account, months and total
+    columns: usize,
+    query: ReportQuery,
+    cost_centers: Vec<String>,
+    accounts: Vec<AccountHibernate>,
+}
+
+/// Income and expenses per month, combined and per cost center, as page,
+/// CSV download or JSON
+pub async fn income_statement(
+    Extension(app): Extension<Arc<PerfinApp>>,
+    Query(query): Query<ReportQuery>,
+) -> Response {
+    let report = {
+        let ledger = app.use_ledger();
+        query
+            .period()
+            .and_then(|period| IncomeStatements::new(&ledger, period, query.cost_center()))
+    };
+    let report = match report {
+        Ok(report) => report,
+        Err(e) => {
+            error!("Income statement failed: {:?}", e);
+            return Html(format!("Error: {}", e)).into_response();
+        }
+    };
+
+    match query.format() {
+        "json" => Json(report).into_response(),
+        "csv" => {
+            let mut csv = vec![];
+            if let Err(e) = report.write_csv(&mut csv) {
+                error!("Income statement CSV failed: {:?}", e);
+                return Html(format!("Error: {}", e)).into_response();
+            }
+            (
+                [
+                    (header::CONTENT_TYPE, "text/csv; charset=utf-8"),
+                    (
+                        header::CONTENT_DISPOSITION,
+                        "attachment; filename=\"income_statement.csv\"",
+                    ),
+                ],
+                csv,
+            )
+                .into_response()
+        }
+        _ => render_html_template(app, "income_statement", |ledger| IncomeStatementContext {
+            columns: report.months.len() + 2,
+            report,
+            query,
+            cost_centers: ledger
+                .cost_centers()
+                .into_iter()
+                .map(str::to_string)
+                .collect(),
+            accounts: ledger.accounts_for_hibernate(),
+        })
+        .into_response(),
+    }
+}
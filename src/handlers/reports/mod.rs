@@ -0,0 +1,45 @@
+mod trial_balance;
+pub use trial_balance::*;
+
+mod income_statement;
+pub use income_statement::*;
+
+mod budget;
+pub use budget::*;
+
+mod charts;
+pub use charts::*;
+
+mod export;
+pub use export::*;
+
+use serde::{Deserialize, Serialize};
+
+use crate::Period;
+
+/// Query parameters shared by the reports
+#[derive(Deserialize, Serialize, Debug, Default)]
+pub struct ReportQuery {
+    /// `month`, `quarter`, `halfyear` or `year` (the default)
+    period: Option<String>,
+    /// Month, quarter or halfyear number
+    number: Option<u32>,
+    cost_center: Option<String>,
+    /// `html` (the default), `json` or, where supported, `csv`
+    format: Option<String>,
+}
+
+impl ReportQuery {
+    fn period(&self) -> crate::Result<Period> {
+        Period::parse(self.period.as_deref().unwrap_or("year"), self.number)
+    }
+
+    /// An empty cost center, as sent by the form, means all cost centers
+    fn cost_center(&self) -> Option<&str> {
+        self.cost_center.as_deref().filter(|code| !code.is_empty())
+    }
+
+    fn format(&self) -> &str {
+        self.format.as_deref().unwrap_or("html")
+    }
+}
@@ -0,0 +1,60 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::{Extension, Query},
+    response::{Html, IntoResponse, Response},
+    Json,
+};
+use serde::Serialize;
+use tracing::error;
+
+use crate::{
+    handlers::render_html_template, AccountHibernate, CostCentersRepository, PerfinApp,
+    TrialBalance,
+};
+
+use super::ReportQuery;
+
+#[derive(Serialize)]
+struct TrialBalanceContext {
+    report: TrialBalance,
+    query: ReportQuery,
+    cost_centers: Vec<String>,
+    accounts: Vec<AccountHibernate>,
+}
+
+/// Opening balance, debit and credit totals and closing balance per account and
+/// cost center, as page or as JSON
+pub async fn trial_balance(
+    Extension(app): Extension<Arc<PerfinApp>>,
+    Query(query): Query<ReportQuery>,
+) -> Response {
+    let report = {
+        let ledger = app.use_ledger();
+        query
+            .period()
+            .and_then(|period| TrialBalance::new(&ledger, period, query.cost_center()))
+    };
+    let report = match report {
+        Ok(report) => report,
+        Err(e) => {
+            error!("Trial balance failed: {:?}", e);
+            return Html(format!("Error: {}", e)).into_response();
+        }
+    };
+
+    match query.format() {
+        "json" => Json(report).into_response(),
+        _ => render_html_template(app, "trial_balance", |ledger| TrialBalanceContext {
+            report,
+            query,
+            cost_centers: ledger
+                .cost_centers()
+                .into_iter()
+                .map(str::to_string)
+                .collect(),
+            accounts: ledger.accounts_for_hibernate(),
+        })
+        .into_response(),
+    }
+}
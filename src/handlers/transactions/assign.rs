@@ -0,0 +1,143 @@
+use std::{str::FromStr, sync::Arc};
+
+use axum::{
+    extract::{Extension, Form},
+    response::{Html, IntoResponse},
+};
+use serde::{Deserialize, Serialize};
+use tracing::{error, info};
+
+use crate::{
+    ing::DescriptionProperties, AccountsRepository, AssignmentReason, BankTransaction,
+    BankTransactionsRepository, Error, ImportBatchStatus, ImportBatchesRepository, Journal,
+    JournalRepository, Ledger, PerfinApp, Split,
+};
+
+#[derive(Deserialize)]
+pub struct ManualAssignment {
+    transaction_id: String,
+    account_code: String,
+    description: Option<String>,
+    /// Splits separated by `;`, in the notation of `Split::from_str`
+    splits: Option<String>,
+}
+
+#[derive(Serialize)]
+struct AssignedContext {
+    #[serde(flatten)]
+    transaction: BankTransaction,
+    /// Header of the group of assigned transactions the row belongs to
+    group: String,
+}
+
+/// Assigns an unassigned bank transaction to an account and registers it in the
+/// journal, or for a staged batch when it is committed; responds with the
+/// updated row for the upload result page
+pub async fn assign(
+    Extension(app): Extension<Arc<PerfinApp>>,
+    Form(assignment): Form<ManualAssignment>,
+) -> impl IntoResponse {
+    let app = app.clone();
+    let mut template_renderer = app.use_template_renderer();
+    let mut ledger = app.use_ledger();
+
+    let context = match assign_transaction(&mut ledger, assignment) {
+        Ok(context) => context,
+        Err(e) => {
+            error!("Manual assignment failed: {:?}", e);
+            return Html(format!("Error: {}", e));
+        }
+    };
+
+    info!(
+        "Assigned transaction {} to {}",
+        context.transaction.id, context.group
+    );
+    match template_renderer.render("bank_transaction", &context) {
+        Ok(html) => Html(html),
+        Err(e) => Html(format!("Error: {}", e)),
+    }
+}
+
+fn assign_transaction(
+    ledger: &mut Ledger,
+    assignment: ManualAssignment,
+) -> crate::Result<AssignedContext> {
+    let mut transaction = find_transaction(ledger, &assignment.transaction_id)
+        .ok_or_else(|| Error::UnrecognisedBankTransaction(assignment.transaction_id.clone()))?;
+    if transaction.account_code.is_some() {
+        return Err(Error::BankTransactionAlreadyAssigned(transaction.id));
+    }
+
+    let group = match ledger.find_account_by_reference(&assignment.account_code) {
+        Some(account) => format!("{} - {}", account.code, account.description),
+        None => return Err(Error::UnrecognisedAccountCode(assignment.account_code)),
+    };
+
+    let splits = parse_splits(assignment.splits.as_deref().unwrap_or_default())?;
+    ledger.check_splits(&splits)?;
+
+    if let Some(description) = assignment.description.filter(|d| !d.trim().is_empty()) {
+        transaction.attributes.insert(
+            DescriptionProperties::DESCRIPTION.to_string(),
+            description.trim().to_string(),
+        );
+    }
+    transaction.account_code = Some(assignment.account_code.clone());
+    transaction.assignment_reason = Some(AssignmentReason::Manual);
+    transaction.assigned_by = None;
+    transaction.assignment_warning = None;
+    transaction.splits = splits;
+
+    if ledger.bank_transactions().get(&transaction.id).is_none() {
+        // Committing the staged batch registers the transaction in the journal
+        ledger.update_staged_transaction(&transaction)?;
+        return Ok(AssignedContext { transaction, group });
+    }
+
+    // Rolling back the batch that added the transaction removes the entry as well
+    let mut journal_entry = ledger.bank_transaction_entry(&transaction)?;
+    journal_entry.batch_id = ledger
+        .import_batches()
+        .into_iter()
+        .find(|batch| {
+            batch.status == ImportBatchStatus::Committed
+                && batch.added_transaction_ids.contains(&transaction.id)
+        })
+        .map(|batch| batch.id.clone());
+    ledger.register(journal_entry)?;
+    ledger.save_journal()?;
+
+    // Keeps committing a staged batch from registering the transaction again
+    ledger.import_bank_transaction(transaction.clone());
+    ledger.save_bank_transactions()?;
+    ledger.update_staged_transaction(&transaction)?;
+
+    Ok(AssignedContext { transaction, group })
+}
+
+fn parse_splits(text: &str) -> crate::Result<Vec<Split>> {
+    text.split(';')
+        .filter(|split| !split.trim().is_empty())
+        .map(Split::from_str)
+        .collect()
+}
+
+/// The transaction as imported before, or else as uploaded in a staged batch
+fn find_transaction(ledger: &Ledger, transaction_id: &str) -> Option<BankTransaction> {
+    if let Some(transaction) = ledger.bank_transactions().get(transaction_id) {
+        return Some(transaction.clone());
+    }
+
+    ledger
+        .import_batches()
+        .into_iter()
+        .filter(|batch| batch.status == ImportBatchStatus::Staged)
+        .find_map(|batch| {
+            batch
+                .transactions
+                .iter()
+                .find(|transaction| transaction.id == transaction_id)
+                .cloned()
+        })
+}
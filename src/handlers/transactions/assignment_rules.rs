@@ -0,0 +1,53 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::{Extension, Query},
+    response::IntoResponse,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{handlers::render_html_template, AssignmentRuleHibernate, PerfinApp};
+
+#[derive(Deserialize, Debug, Default)]
+pub struct AssignmentRulesQuery {
+    section: Option<String>,
+    key: Option<String>,
+    /// Tells apart the rules of a name or description key
+    pattern: Option<String>,
+}
+
+#[derive(Serialize)]
+struct AssignmentRulesContext {
+    rules: Vec<AssignmentRuleHibernate>,
+    /// Only the rule with the section, key and pattern of the query is shown
+    filtered: bool,
+}
+
+/// The assignment rules in the order they are tried, or the single rule given
+/// by section, key and pattern
+pub async fn assignment_rules(
+    Extension(app): Extension<Arc<PerfinApp>>,
+    Query(query): Query<AssignmentRulesQuery>,
+) -> impl IntoResponse {
+    render_html_template(app, "assignment_rules", move |ledger| {
+        let filtered = query.section.is_some() || query.key.is_some() || query.pattern.is_some();
+        AssignmentRulesContext {
+            rules: ledger
+                .assignment_rules_for_hibernate()
+                .into_iter()
+                .filter(|rule| {
+                    query
+                        .section
+                        .as_ref()
+                        .is_none_or(|section| &rule.section == section)
+                        && query.key.as_ref().is_none_or(|key| &rule.key == key)
+                        && query
+                            .pattern
+                            .as_ref()
+                            .is_none_or(|pattern| rule.pattern.as_ref() == Some(pattern))
+                })
+                .collect(),
+            filtered,
+        }
+    })
+}
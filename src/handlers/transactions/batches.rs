@@ -0,0 +1,103 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::{Extension, Path},
+    response::{Html, IntoResponse, Redirect},
+};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use tracing::info;
+
+use crate::{
+    handlers::render_html_template, AccountHibernate, ImportBatch, ImportBatchStatus,
+    ImportBatchesRepository, PerfinApp,
+};
+
+const BATCHES_PATH: &str = "/transactions/batches";
+
+#[derive(Serialize)]
+struct ImportBatchSummary {
+    id: String,
+    uploader: String,
+    file_name: String,
+    format_code: String,
+    timestamp: DateTime<Utc>,
+    checksum: String,
+    status: ImportBatchStatus,
+    transactions: usize,
+    journal_entries: usize,
+}
+
+#[derive(Serialize)]
+struct ImportBatchesContext {
+    batches: Vec<ImportBatchSummary>,
+    accounts: Vec<AccountHibernate>,
+}
+
+impl ImportBatchSummary {
+    fn new(batch: &ImportBatch, journal_entries: usize) -> Self {
+        Self {
+            id: batch.id.clone(),
+            uploader: batch.uploader.clone(),
+            file_name: batch.file_name.clone(),
+            format_code: batch.format_code.clone(),
+            timestamp: batch.timestamp,
+            checksum: batch.checksum.clone(),
+            status: batch.status,
+            transactions: batch.transactions.len(),
+            journal_entries,
+        }
+    }
+}
+
+pub async fn batches(Extension(app): Extension<Arc<PerfinApp>>) -> impl IntoResponse {
+    render_html_template(app, "import_batches", |ledger| ImportBatchesContext {
+        batches: ledger
+            .import_batches()
+            .into_iter()
+            .map(|batch| {
+                let journal_entries = ledger
+                    .journal
+                    .iter()
+                    .filter(|entry| entry.batch_id.as_deref() == Some(batch.id.as_str()))
+                    .count();
+                ImportBatchSummary::new(batch, journal_entries)
+            })
+            .collect(),
+        accounts: ledger.accounts_for_hibernate(),
+    })
+}
+
+pub async fn commit_batch(
+    Extension(app): Extension<Arc<PerfinApp>>,
+    Path(batch_id): Path<String>,
+) -> Result<Redirect, Html<String>> {
+    let mut ledger = app.use_ledger();
+    match ledger.commit_import_batch(&batch_id) {
+        Ok(created) => {
+            info!(
+                "Committed import batch {}: {} journal entries",
+                batch_id, created
+            );
+            Ok(Redirect::to(BATCHES_PATH))
+        }
+        Err(e) => Err(Html(format!("Error: {}", e))),
+    }
+}
+
+pub async fn rollback_batch(
+    Extension(app): Extension<Arc<PerfinApp>>,
+    Path(batch_id): Path<String>,
+) -> Result<Redirect, Html<String>> {
+    let mut ledger = app.use_ledger();
+    match ledger.rollback_import_batch(&batch_id) {
+        Ok(removed) => {
+            info!(
+                "Rolled back import batch {}: {} journal entries removed",
+                batch_id, removed
+            );
+            Ok(Redirect::to(BATCHES_PATH))
+        }
+        Err(e) => Err(Html(format!("Error: {}", e))),
+    }
+}
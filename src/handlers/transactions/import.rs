@@ -4,7 +4,9 @@ use axum::{extract::Extension, response::IntoResponse};
 
 use serde::Serialize;
 
-use crate::{handlers::render_html_template, AccountHibernate, BankFormats, PerfinApp};
+use crate::{
+    handlers::render_html_template, AccountHibernate, BankFormats, BankFormatsRepository, PerfinApp,
+};
 
 #[derive(Serialize)]
 struct ImportContext {
@@ -16,9 +18,10 @@ pub async fn import(
     Extension(app): Extension<Arc<PerfinApp>>,
     // Query(selected): Query<SelectedTemplate>,
 ) -> impl IntoResponse {
-    let formats = BankFormats::from_fixture().unwrap_or_else(|_| BankFormats::default());
     render_html_template(app, "statements_import", |ledger| ImportContext {
-        formats,
+        formats: BankFormats {
+            formats: ledger.bank_formats().clone(),
+        },
         accounts: ledger.accounts_for_hibernate(),
     })
 }
@@ -3,3 +3,12 @@ pub use import::*;
 
 mod upload;
 pub use upload::*;
+
+mod batches;
+pub use batches::*;
+
+mod assign;
+pub use assign::*;
+
+mod assignment_rules;
+pub use assignment_rules::*;
@@ -1,39 +1,29 @@
-#![allow(unused_imports)]
-#![allow(dead_code)]
-
-use std::{
-    borrow::Borrow, collections::HashMap, ffi::OsStr, fmt::Display, io::BufReader, ops::Deref,
-    path::Path, sync::Arc,
-};
+use std::{collections::HashMap, ffi::OsStr, fmt::Display, ops::Deref, path::Path, sync::Arc};
 
 use axum::{
     body::Bytes,
     extract::{multipart::Field, ContentLengthLimit, Extension, Multipart},
     response::{Html, IntoResponse},
-    Json,
 };
 
-use handlebars::Context;
-use rand::random;
+use chrono::Utc;
 use serde::Serialize;
-use tracing::{debug, error, info};
+use sha2::{Digest, Sha256};
+use tracing::{error, info};
 
 use crate::{
-    html_template_renderer::HtmlTemplateRenderer,
-    ing::{DescriptionProperties, IngImporter, IngTransaction},
-    AccountHibernate, AccountsRepository, BankTransaction, CostCentersRepository, Error, PerfinApp,
+    budget_overruns, verify_continuity, AccountHibernate, AccountsRepository,
+    BankFormatsRepository, BankTransaction, BankTransactionsRepository, BudgetOverrun,
+    ContinuityWarning, Error, ImportBatch, ImportBatchStatus, ImportBatchesRepository,
+    ImportCounts, ImportStatus, JournalEntry, PerfinApp,
 };
 
-const SAVE_FILE_BASE_PATH: &str = "./data/storage/upload";
-
-#[derive(PartialEq)]
-enum BankImportType {
-    Unrecognised,
-    IngCsv,
-}
-
 enum ContentType {
     Csv,
+    Tab,
+    Xml,
+    Swift,
+    Ofx,
     Json,
 }
 
@@ -44,7 +34,8 @@ struct FileAttachment {
 }
 
 struct FormData {
-    import_type: BankImportType,
+    format_code: Option<String>,
+    uploader: Option<String>,
     attachment: FileAttachment,
 }
 
@@ -56,9 +47,14 @@ struct Indices {
 
 #[derive(Serialize)]
 struct UploadContext {
+    batch_id: Option<String>,
     accounts: Vec<AccountHibernate>,
     indices: Indices,
+    counts: ImportCounts,
     failures: Option<Vec<String>>,
+    warnings: Option<Vec<String>>,
+    continuity: Option<Vec<ContinuityWarning>>,
+    over_budget: Option<Vec<BudgetOverrun>>,
     imported: Option<HashMap<String, Vec<BankTransaction>>>,
     assigned: Option<HashMap<String, Vec<BankTransaction>>>,
 }
@@ -74,12 +70,59 @@ pub async fn upload(
 ) -> impl IntoResponse {
     info!("Upload request");
     if let Some(form_data) = FormData::from_mime(&mut multipart).await {
+        info!(
+            "\tfile {} ({})",
+            form_data.attachment.filename, form_data.attachment.content_type
+        );
         let app = app.clone();
         let mut template_renderer = app.use_template_renderer();
-        let /*mut*/ ledger = app.use_ledger();
-
-        let data: &[u8] = &form_data.attachment.contents.as_ref();
-        let mut importer = IngImporter::from_reader(data);
+        let mut ledger = app.use_ledger();
+
+        let data: &[u8] = form_data.attachment.contents.as_ref();
+        let detected = ledger.detect_bank_format(data);
+        let mut warnings = vec![];
+
+        let format_code = match form_data.format_code {
+            Some(format_code) => {
+                match detected.first() {
+                    Some(best) if best.format_code != format_code => warnings.push(format!(
+                        "The file looks more like format '{}' than the chosen '{}'",
+                        best.format_code, format_code
+                    )),
+                    None => warnings.push(format!(
+                        "The file does not look like format '{}'",
+                        format_code
+                    )),
+                    _ => {}
+                }
+                format_code
+            }
+            None => match detected.first() {
+                Some(best) => {
+                    info!(
+                        "\tdetected format {} ({:.2})",
+                        best.format_code, best.confidence
+                    );
+                    best.format_code.clone()
+                }
+                None => {
+                    return Html(format!(
+                        "Error: {}",
+                        Error::UndetectedBankFormat(form_data.attachment.filename.clone())
+                    ))
+                }
+            },
+        };
+
+        let importer = match ledger.find_bank_importer(&format_code) {
+            Some(importer) => importer,
+            None => {
+                return Html(format!(
+                    "Error: {}",
+                    Error::UnrecognisedBankFormat(format_code)
+                ))
+            }
+        };
 
         let mut failures = vec![];
         let mut imported: HashMap<String, Vec<BankTransaction>> = HashMap::new();
@@ -88,16 +131,45 @@ pub async fn upload(
         info!("\tstart parsing transactions");
 
         let ledger_ref = ledger.deref();
-        for result in importer.transactions(ledger_ref, ledger_ref, ledger_ref) {
+        let results: Vec<_> = importer
+            .transactions(Box::new(data), ledger_ref, ledger_ref, ledger_ref)
+            .collect();
+
+        let uploaded: Vec<BankTransaction> = results
+            .iter()
+            .filter_map(|result| result.as_ref().ok())
+            .cloned()
+            .collect();
+        let continuity = verify_continuity(&uploaded, ledger.bank_transactions());
+        let conflicts = uploaded
+            .iter()
+            .filter(|transaction| transaction.assignment_warning.is_some())
+            .count();
+        if conflicts > 0 {
+            warnings.push(format!(
+                "{} transaction(s) matched assignment rules with different accounts",
+                conflicts
+            ));
+        }
+
+        let mut counts = ImportCounts::default();
+        for result in results {
             match result {
                 Ok(bank_transaction) => {
+                    counts.count(ledger.bank_transactions().status(&bank_transaction));
+
                     let transaction_account_code = bank_transaction.account_code.clone();
                     if let Some(account_code) = transaction_account_code {
-                        let account = ledger
-                            .find_account_by_reference(account_code.as_str())
-                            .unwrap();
+                        let account = match ledger.find_account_by_reference(account_code.as_str())
+                        {
+                            Some(account) => account,
+                            None => {
+                                failures.push(Error::UnrecognisedAccountCode(account_code));
+                                continue;
+                            }
+                        };
                         let key = format!("{} - {}", account_code, account.description);
-                        let entry = assigned.entry(key).or_insert_with(Vec::default);
+                        let entry = assigned.entry(key).or_default();
                         (*entry).push(bank_transaction);
                     } else {
                         let optional_relation_name = bank_transaction.relation_name.clone();
@@ -105,7 +177,7 @@ pub async fn upload(
                             Some(key) => key,
                             None => String::from("[Unknown]"),
                         };
-                        let entry = imported.entry(key).or_insert_with(Vec::default);
+                        let entry = imported.entry(key).or_default();
                         (*entry).push(bank_transaction);
                     }
                 }
@@ -113,27 +185,81 @@ pub async fn upload(
             }
         }
 
+        let new_entries: Vec<JournalEntry> = uploaded
+            .iter()
+            .filter(|transaction| {
+                transaction.account_code.is_some()
+                    && ledger.bank_transactions().status(transaction) == ImportStatus::New
+            })
+            .filter_map(|transaction| ledger.bank_transaction_entry(transaction).ok())
+            .collect();
+        let over_budget = budget_overruns(&ledger, &new_entries);
+
+        // TODO retrieve the uploader from the Session instead of the upload form
+        let batch = ImportBatch {
+            id: uuid::Uuid::new_v4().simple().to_string(),
+            uploader: form_data
+                .uploader
+                .unwrap_or_else(|| String::from("[Unknown]")),
+            file_name: form_data.attachment.filename.clone(),
+            format_code,
+            timestamp: Utc::now(),
+            checksum: base16ct::lower::encode_string(&Sha256::digest(data)),
+            status: ImportBatchStatus::Staged,
+            committed_at: None,
+            transactions: uploaded,
+            added_transaction_ids: vec![],
+            replaced_transactions: vec![],
+            replaced_journal_entries: vec![],
+        };
+        let batch_id = batch.id.clone();
+        let batch_id = match ledger.stage_import_batch(batch) {
+            Ok(()) => Some(batch_id),
+            Err(e) => {
+                error!("Staging the import batch failed: {:?}", e);
+                failures.push(e);
+                None
+            }
+        };
+
         info!("\tparsed; rendering");
 
         let html_text = template_renderer
             .render(
                 "upload_result",
                 &UploadContext {
-                    failures: if failures.len() > 0 {
+                    failures: if !failures.is_empty() {
                         Some(failures.iter().map(|e| format!("{:?}", e)).collect())
                     } else {
                         None
                     },
-                    imported: if imported.len() > 0 {
+                    warnings: if !warnings.is_empty() {
+                        Some(warnings)
+                    } else {
+                        None
+                    },
+                    continuity: if !continuity.is_empty() {
+                        Some(continuity)
+                    } else {
+                        None
+                    },
+                    over_budget: if !over_budget.is_empty() {
+                        Some(over_budget)
+                    } else {
+                        None
+                    },
+                    imported: if !imported.is_empty() {
                         Some(imported)
                     } else {
                         None
                     },
-                    assigned: if assigned.len() > 0 {
+                    assigned: if !assigned.is_empty() {
                         Some(assigned)
                     } else {
                         None
                     },
+                    batch_id,
+                    counts,
                     accounts: ledger.accounts_for_hibernate(),
                     indices: Indices {
                         imported: 0,
@@ -151,29 +277,14 @@ pub async fn upload(
     }
 }
 
-impl From<&str> for BankImportType {
-    fn from(str: &str) -> Self {
-        if "ing".eq_ignore_ascii_case(str) {
-            Self::IngCsv
-        } else {
-            Self::Unrecognised
-        }
-    }
-}
-
-impl Display for BankImportType {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match self {
-            BankImportType::Unrecognised => f.write_str("unrecognised"),
-            BankImportType::IngCsv => f.write_str("ing"),
-        }
-    }
-}
-
 impl Display for ContentType {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             ContentType::Csv => f.write_str("text/csv"),
+            ContentType::Tab => f.write_str("text/tab-separated-values"),
+            ContentType::Xml => f.write_str("application/xml"),
+            ContentType::Swift => f.write_str("application/x-swift-mt940"),
+            ContentType::Ofx => f.write_str("application/x-ofx"),
             ContentType::Json => f.write_str("application/json"),
         }
     }
@@ -183,22 +294,23 @@ impl ContentType {
     pub fn from_extension(ext: &str) -> Option<Self> {
         if "csv".eq(ext) {
             Some(Self::Csv)
+        } else if "tab".eq_ignore_ascii_case(ext) || "txt".eq_ignore_ascii_case(ext) {
+            Some(Self::Tab)
+        } else if "xml".eq_ignore_ascii_case(ext) {
+            Some(Self::Xml)
+        } else if ["sta", "940", "mt940"]
+            .iter()
+            .any(|swift_ext| swift_ext.eq_ignore_ascii_case(ext))
+        {
+            Some(Self::Swift)
+        } else if "ofx".eq_ignore_ascii_case(ext) || "qfx".eq_ignore_ascii_case(ext) {
+            Some(Self::Ofx)
         } else if "json".eq_ignore_ascii_case(ext) {
             Some(Self::Json)
         } else {
             None
         }
     }
-
-    pub fn from_header(hdr: &str) -> Option<Self> {
-        if "text/csv".eq_ignore_ascii_case(hdr) {
-            Some(Self::Csv)
-        } else if "application/json".eq_ignore_ascii_case(hdr) {
-            Some(Self::Json)
-        } else {
-            None
-        }
-    }
 }
 
 impl FileAttachment {
@@ -208,10 +320,9 @@ impl FileAttachment {
             let ext = osfilename
                 .extension()
                 .and_then(OsStr::to_str)
-                .or_else(|| Some(".unknown"))
-                .unwrap();
+                .unwrap_or(".unknown");
             if let Some(content_type) = ContentType::from_extension(ext) {
-                return Some((format!("{}", field_filename), content_type));
+                return Some((field_filename.to_string(), content_type));
             }
         }
 
@@ -236,10 +347,11 @@ impl FileAttachment {
 
 impl FormData {
     pub async fn from_mime(multipart: &mut Multipart) -> Option<Self> {
-        let mut import_type: Option<BankImportType> = None;
+        let mut format_code: Option<String> = None;
+        let mut uploader: Option<String> = None;
         let mut attachment: Option<FileAttachment> = None;
         let mut has_fields = true;
-        while has_fields && (import_type.is_none() || attachment.is_none()) {
+        while has_fields {
             let next_field_result = multipart.next_field().await;
             match next_field_result {
                 Ok(next_field_option) => match next_field_option {
@@ -247,10 +359,15 @@ impl FormData {
                         if let Some(name) = field.name() {
                             if "format".eq_ignore_ascii_case(name) {
                                 let data = field.text().await;
-                                if let Ok(import_type_code) = data {
-                                    let candidate = BankImportType::from(import_type_code.as_str());
-                                    if candidate != BankImportType::Unrecognised {
-                                        import_type = Some(candidate);
+                                if let Ok(candidate) = data {
+                                    if !candidate.is_empty() {
+                                        format_code = Some(candidate);
+                                    }
+                                }
+                            } else if "uploader".eq_ignore_ascii_case(name) {
+                                if let Ok(candidate) = field.text().await {
+                                    if !candidate.is_empty() {
+                                        uploader = Some(candidate);
                                     }
                                 }
                             } else if "transactions_file".eq_ignore_ascii_case(name) {
@@ -266,15 +383,10 @@ impl FormData {
             }
         }
 
-        if let Some(import_type) = import_type {
-            if let Some(attachment) = attachment {
-                return Some(Self {
-                    attachment,
-                    import_type,
-                });
-            }
-        }
-
-        None
+        attachment.map(|attachment| Self {
+            attachment,
+            format_code,
+            uploader,
+        })
     }
 }
@@ -46,6 +46,10 @@ handlebars_helper!(selected_if: |current: String, selected_code: Option<String>
     result
 });
 
+handlebars_helper!(url_encode: |text: String| {
+    url::form_urlencoded::byte_serialize(text.as_bytes()).collect::<String>()
+});
+
 impl HtmlTemplateRenderer {
     pub fn new() -> Result<Self, TemplateError> {
         let mut result = Self {
@@ -68,6 +72,8 @@ impl HtmlTemplateRenderer {
             .register_helper("to_json", Box::new(to_json));
         self.handlebars
             .register_helper("selected_if", Box::new(selected_if));
+        self.handlebars
+            .register_helper("url_encode", Box::new(url_encode));
 
         register_files_from("./templates", &mut |partial_name, partial_file| {
             debug!("Template '{}' -> {}", partial_name, partial_file);
@@ -13,3 +13,6 @@ pub use models::*;
 
 mod repositories;
 pub use repositories::*;
+
+mod reports;
+pub use reports::*;
@@ -9,7 +9,7 @@ use axum::{
 
 use http::HeaderValue;
 use hyper::StatusCode;
-use perfin::{handlers, HtmlTemplateRenderer, Ledger, PerfinApp};
+use perfin::{export_journal, handlers, ExportFormat, HtmlTemplateRenderer, Ledger, PerfinApp};
 use tower_http::{
     services::{ServeDir, ServeFile},
     set_header::SetResponseHeaderLayer,
@@ -31,6 +31,30 @@ async fn main() {
     // TODO retrieve infrom from Session
     let ledger = Ledger::load("cb09add43080499a90e7479543e750a9", 2022).expect("load ledger");
 
+    // `perfin export <format> [file]` writes the journal instead of serving,
+    // `perfin lint` reports the assignment rules that can never match
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match args.first().map(String::as_str) {
+        Some("export") => {
+            if let Err(e) = export(&ledger, &args[1..]) {
+                error!("Export failed: {}", e);
+                std::process::exit(1);
+            }
+            return;
+        }
+        Some("lint") => {
+            let findings = ledger.lint_assignment_rules();
+            for finding in findings.iter() {
+                println!("{}", finding);
+            }
+            if !findings.is_empty() {
+                std::process::exit(1);
+            }
+            return;
+        }
+        _ => {}
+    }
+
     let content_security_policy = SetResponseHeaderLayer::if_not_present(
         http::header::CONTENT_SECURITY_POLICY,
         HeaderValue::from_static(
@@ -71,6 +95,45 @@ async fn main() {
                     "/upload/bank_transactions",
                     POST(handlers::transactions::upload),
                 )
+                .route("/transactions/assign", POST(handlers::transactions::assign))
+                .route(
+                    "/transactions/batches",
+                    GET(handlers::transactions::batches),
+                )
+                .route(
+                    "/transactions/batches/:batch_id/commit",
+                    POST(handlers::transactions::commit_batch),
+                )
+                .route(
+                    "/transactions/batches/:batch_id/rollback",
+                    POST(handlers::transactions::rollback_batch),
+                )
+                .route(
+                    "/transactions/assignment_rules",
+                    GET(handlers::transactions::assignment_rules),
+                )
+                .route(
+                    "/reports/trial_balance",
+                    GET(handlers::reports::trial_balance),
+                )
+                .route(
+                    "/reports/income_statement",
+                    GET(handlers::reports::income_statement),
+                )
+                .route("/reports/budget", GET(handlers::reports::budget))
+                .route("/export/:format", GET(handlers::reports::export))
+                .route(
+                    "/charts/account/:account_code",
+                    GET(handlers::reports::account_chart_svg),
+                )
+                .route(
+                    "/charts/income_expense",
+                    GET(handlers::reports::income_expense_chart_svg),
+                )
+                .route(
+                    "/charts/bank_balance/:iban",
+                    GET(handlers::reports::bank_balance_chart_svg),
+                )
                 // .route("/greet/:name", GET(greet))
                 // .route("/template/:template/image/:image_id", GET(image))
                 .layer(
@@ -90,3 +153,15 @@ async fn main() {
         Err(template_error) => error!("Handlebars error {}", template_error),
     }
 }
+
+fn export(ledger: &Ledger, args: &[String]) -> perfin::Result<()> {
+    let format: ExportFormat = args
+        .first()
+        .map(String::as_str)
+        .unwrap_or_default()
+        .parse()?;
+    match args.get(1) {
+        Some(file_name) => export_journal(ledger, format, std::fs::File::create(file_name)?),
+        None => export_journal(ledger, format, std::io::stdout().lock()),
+    }
+}
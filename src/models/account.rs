@@ -1,16 +1,187 @@
+use std::collections::{HashMap, HashSet};
+
+use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
 
+use crate::Error;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum AccountType {
+    Asset,
+    Liability,
+    Equity,
+    Income,
+    Expense,
+}
+
+impl AccountType {
+    pub fn normal_balance(&self) -> NormalBalance {
+        match self {
+            AccountType::Asset | AccountType::Expense => NormalBalance::Debit,
+            AccountType::Liability | AccountType::Equity | AccountType::Income => {
+                NormalBalance::Credit
+            }
+        }
+    }
+}
+
+/// The side on which the balance of an account normally is
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum NormalBalance {
+    Debit,
+    Credit,
+}
+
+impl NormalBalance {
+    /// A journal balance (debit positive) as seen from this side
+    pub fn presented(&self, balance: Decimal) -> Decimal {
+        match self {
+            NormalBalance::Debit => balance,
+            NormalBalance::Credit => -balance,
+        }
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Account {
     #[serde(skip)]
     pub code: String,
     pub description: String,
+    /// Inherited from the parent when not given
+    #[serde(rename = "type", default, skip_serializing_if = "Option::is_none")]
+    pub account_type: Option<AccountType>,
+    #[serde(rename = "parent", default, skip_serializing_if = "Option::is_none")]
+    pub parent_code: Option<String>,
+    /// Only needed when it differs from the type, e.g. for contra accounts
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub normal_balance: Option<NormalBalance>,
+}
+
+impl Account {
+    pub fn normal_balance(&self) -> NormalBalance {
+        match (self.normal_balance, self.account_type) {
+            (Some(normal_balance), _) => normal_balance,
+            (None, Some(account_type)) => account_type.normal_balance(),
+            (None, None) => NormalBalance::Debit,
+        }
+    }
+}
+
+/// Checks that the parents exist, do not form a cycle and have the same type as
+/// their children; accounts without a type get the type of their parent
+pub fn resolve_account_tree(accounts: &mut HashMap<String, Account>) -> crate::Result<()> {
+    let mut inherited_types = vec![];
+    for account in accounts.values() {
+        let mut visited = HashSet::from([account.code.as_str()]);
+        let mut nearest_type: Option<(&str, AccountType)> = None;
+        let mut current = account;
+        while let Some(parent_code) = &current.parent_code {
+            let parent =
+                accounts
+                    .get(parent_code)
+                    .ok_or_else(|| Error::UnrecognisedParentAccount {
+                        account_code: current.code.clone(),
+                        parent_code: parent_code.clone(),
+                    })?;
+            if !visited.insert(parent.code.as_str()) {
+                return Err(Error::AccountHierarchyCycle(account.code.clone()));
+            }
+            if let (None, Some(parent_type)) = (nearest_type, parent.account_type) {
+                nearest_type = Some((parent.code.as_str(), parent_type));
+            }
+            current = parent;
+        }
+
+        match (account.account_type, nearest_type) {
+            (Some(account_type), Some((parent_code, parent_type)))
+                if account_type != parent_type =>
+            {
+                return Err(Error::IncompatibleAccountType {
+                    account_code: account.code.clone(),
+                    account_type,
+                    parent_code: parent_code.to_string(),
+                    parent_type,
+                })
+            }
+            (None, Some((_, parent_type))) => {
+                inherited_types.push((account.code.clone(), parent_type))
+            }
+            _ => {}
+        }
+    }
+
+    for (account_code, account_type) in inherited_types {
+        if let Some(account) = accounts.get_mut(&account_code) {
+            account.account_type = Some(account_type);
+        }
+    }
+
+    Ok(())
+}
+
+/// The accounts depth first, children sorted by code, with their depth in the tree
+pub fn account_tree(accounts: &HashMap<String, Account>) -> Vec<(usize, &Account)> {
+    let mut children: HashMap<Option<&str>, Vec<&Account>> = HashMap::new();
+    for account in accounts.values() {
+        children
+            .entry(account.parent_code.as_deref())
+            .or_default()
+            .push(account);
+    }
+    for siblings in children.values_mut() {
+        siblings.sort_by(|one, other| one.code.cmp(&other.code));
+    }
+
+    let mut tree = vec![];
+    let mut stack: Vec<(usize, &Account)> = children
+        .get(&None)
+        .map(|roots| roots.iter().rev().map(|root| (0, *root)).collect())
+        .unwrap_or_default();
+    while let Some((depth, account)) = stack.pop() {
+        tree.push((depth, account));
+        if let Some(siblings) = children.get(&Some(account.code.as_str())) {
+            stack.extend(siblings.iter().rev().map(|child| (depth + 1, *child)));
+        }
+    }
+
+    tree
+}
+
+/// Balance of every account including the balances of its descendants
+pub fn account_subtotals(
+    accounts: &HashMap<String, Account>,
+    balances: &HashMap<String, Decimal>,
+) -> HashMap<String, Decimal> {
+    let mut subtotals: HashMap<String, Decimal> = HashMap::new();
+    for (account_code, balance) in balances {
+        let mut current = accounts.get(account_code);
+        while let Some(account) = current {
+            *subtotals.entry(account.code.clone()).or_default() += balance;
+            current = account
+                .parent_code
+                .as_ref()
+                .and_then(|parent_code| accounts.get(parent_code));
+        }
+    }
+
+    subtotals
 }
 
 #[derive(Debug, Serialize)]
 pub struct AccountHibernate {
     pub code: String,
     pub description: String,
+    pub account_type: Option<AccountType>,
+    pub parent_code: Option<String>,
+    pub normal_balance: NormalBalance,
+    pub depth: usize,
+    /// Non-breaking spaces to indent the account in lists and dropdowns
+    pub indent: String,
+    pub has_children: bool,
+    /// Balance of the account and its descendants, as seen from its normal balance
+    pub subtotal: String,
 }
 
 impl From<&Account> for AccountHibernate {
@@ -18,6 +189,13 @@ impl From<&Account> for AccountHibernate {
         Self {
             code: model.code.clone(),
             description: model.description.clone(),
+            account_type: model.account_type,
+            parent_code: model.parent_code.clone(),
+            normal_balance: model.normal_balance(),
+            depth: 0,
+            indent: String::new(),
+            has_children: false,
+            subtotal: String::new(),
         }
     }
 }
@@ -1,9 +1,15 @@
 use serde::{Deserialize, Serialize};
 
+use crate::{AmountCondition, Split};
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct AssignByContractDefinition {
     #[serde(rename = "account")]
     pub account_code: String,
     pub description: Option<String>,
     pub note: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub splits: Vec<Split>,
+    #[serde(flatten)]
+    pub amount: AmountCondition,
 }
@@ -1,11 +1,6 @@
-use regex::Regex;
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug)]
-pub struct AssignByDescription {
-    pub account_code: String,
-    pub search_expression: Regex,
-}
+use crate::{AmountCondition, Split};
 
 #[derive(Deserialize, Debug, Serialize)]
 pub struct AssignByDescriptionDefinition {
@@ -14,16 +9,8 @@ pub struct AssignByDescriptionDefinition {
     #[serde(rename = "search")]
     pub search_expression: String,
     pub note: Option<String>,
-}
-
-impl TryFrom<&AssignByDescriptionDefinition> for AssignByDescription {
-    type Error = regex::Error;
-
-    fn try_from(value: &AssignByDescriptionDefinition) -> core::result::Result<Self, Self::Error> {
-        let search_expression = Regex::new(format!("(?i){}", value.search_expression).as_str())?;
-        Ok(Self {
-            account_code: value.account_code.clone(),
-            search_expression,
-        })
-    }
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub splits: Vec<Split>,
+    #[serde(flatten)]
+    pub amount: AmountCondition,
 }
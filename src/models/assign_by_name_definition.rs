@@ -0,0 +1,31 @@
+use serde::{Deserialize, Serialize};
+
+use crate::AmountCondition;
+
+/// A search term of the `assign_by_name` section: either just the term, or the
+/// term with conditions on the amount
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(untagged)]
+pub enum AssignByNameDefinition {
+    Search(String),
+    Conditional {
+        search: String,
+        #[serde(flatten)]
+        amount: AmountCondition,
+    },
+}
+
+impl AssignByNameDefinition {
+    pub fn search_term(&self) -> &str {
+        match self {
+            Self::Search(search) | Self::Conditional { search, .. } => search,
+        }
+    }
+
+    pub fn amount(&self) -> AmountCondition {
+        match self {
+            Self::Search(_) => AmountCondition::default(),
+            Self::Conditional { amount, .. } => amount.clone(),
+        }
+    }
+}
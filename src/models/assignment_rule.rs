@@ -0,0 +1,775 @@
+use std::collections::HashMap;
+
+use chrono::{Datelike, NaiveDate, Weekday};
+use indexmap::IndexMap;
+use regex::Regex;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    AssignByContractDefinition, AssignByDescriptionDefinition, AssignByNameDefinition,
+    AssignmentReason, Error, Split,
+};
+
+/// Priorities of the rules of the `assign_by_*` sections; between these a
+/// description rule beats a contract, a contract a tag and a tag a relation name
+pub const DESCRIPTION_PRIORITY: i32 = 400;
+pub const CONTRACT_PRIORITY: i32 = 300;
+pub const TAG_PRIORITY: i32 = 200;
+pub const NAME_PRIORITY: i32 = 100;
+
+/// How the importer chooses between matching rules that assign different accounts
+#[derive(Serialize, Deserialize, Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum AssignmentPolicy {
+    /// The rule with the highest priority
+    #[default]
+    First,
+    /// None of them, the transaction is left for manual assignment
+    Strict,
+    /// A contract rule, otherwise the rule with the highest priority
+    PreferContract,
+}
+
+impl AssignmentPolicy {
+    /// The rule to apply out of the matching ones, which are in priority order
+    pub fn choose<'m, 'r>(
+        &self,
+        matches: &'m [(&'r AssignmentRule, RuleMatch)],
+    ) -> Option<&'m (&'r AssignmentRule, RuleMatch)> {
+        match self {
+            Self::Strict if conflict(matches).is_some() => None,
+            Self::PreferContract => matches
+                .iter()
+                .find(|(rule, _)| rule.reason == AssignmentReason::Contract)
+                .or_else(|| matches.first()),
+            _ => matches.first(),
+        }
+    }
+}
+
+/// Highest priority first; the sort is stable, so equal priorities keep the
+/// order of ledger.yaml
+pub fn sort_by_priority(rules: &mut [AssignmentRule]) {
+    rules.sort_by_key(|rule| std::cmp::Reverse(rule.priority));
+}
+
+/// The matching rules do not all assign the same account
+pub fn conflict(matches: &[(&AssignmentRule, RuleMatch)]) -> Option<Error> {
+    let (first, _) = matches.first()?;
+    if matches
+        .iter()
+        .all(|(rule, _)| rule.account_code == first.account_code)
+    {
+        return None;
+    }
+
+    let rules: Vec<String> = matches
+        .iter()
+        .map(|(rule, _)| format!("{} assigns {}", rule.source(), rule.account_code))
+        .collect();
+    Some(Error::ConflictingAccountCodes {
+        rules: rules.join(", "),
+    })
+}
+
+/// Whether money leaves (`debit`) or enters (`credit`) the bank account, as in
+/// the debit/credit columns of the bank exports
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Direction {
+    Debit,
+    Credit,
+}
+
+/// Conditions on the size and sign of the bank amount
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq)]
+pub struct AmountCondition {
+    /// The amount without sign
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub exact_amount: Option<Decimal>,
+    /// Lower bound of the amount without sign, inclusive
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_amount: Option<Decimal>,
+    /// Upper bound of the amount without sign, inclusive
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_amount: Option<Decimal>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub direction: Option<Direction>,
+}
+
+impl AmountCondition {
+    /// Checks the bounds of the rule with the given section and key
+    pub fn validate(&self, rule: &str) -> crate::Result<()> {
+        let invalid = |reason: &str| {
+            Err(Error::InvalidAssignmentRule {
+                rule: rule.to_string(),
+                reason: reason.to_string(),
+            })
+        };
+
+        let amounts = [self.exact_amount, self.min_amount, self.max_amount];
+        if amounts.iter().flatten().any(Decimal::is_sign_negative) {
+            return invalid("amounts are without sign, use direction for debit or credit");
+        }
+        match (self.exact_amount, self.min_amount, self.max_amount) {
+            (Some(_), Some(_), _) | (Some(_), _, Some(_)) => {
+                invalid("exact_amount can not be combined with min_amount or max_amount")
+            }
+            (None, Some(min_amount), Some(max_amount)) if min_amount > max_amount => {
+                invalid("min_amount is above max_amount")
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Lower and, if any, upper bound of the amount without sign
+    fn bounds(&self) -> (Decimal, Option<Decimal>) {
+        match self.exact_amount {
+            Some(exact_amount) => (exact_amount, Some(exact_amount)),
+            None => (self.min_amount.unwrap_or_default(), self.max_amount),
+        }
+    }
+
+    /// Every amount that meets the other condition also meets this one
+    fn includes(&self, other: &Self) -> bool {
+        let (min_amount, max_amount) = self.bounds();
+        let (other_min_amount, other_max_amount) = other.bounds();
+        self.direction
+            .is_none_or(|direction| other.direction == Some(direction))
+            && min_amount <= other_min_amount
+            && max_amount.is_none_or(|max_amount| {
+                other_max_amount.is_some_and(|other_max_amount| other_max_amount <= max_amount)
+            })
+    }
+
+    pub fn matches(&self, amount: Decimal) -> bool {
+        let size = amount.abs();
+        let direction = if amount.is_sign_negative() {
+            Direction::Debit
+        } else {
+            Direction::Credit
+        };
+        self.exact_amount
+            .is_none_or(|exact_amount| size == exact_amount)
+            && self.min_amount.is_none_or(|min_amount| size >= min_amount)
+            && self.max_amount.is_none_or(|max_amount| size <= max_amount)
+            && self.direction.is_none_or(|wanted| wanted == direction)
+    }
+}
+
+/// The `when` of a rule of the `assignment_rules` section; all given conditions
+/// must hold
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct RuleConditionsDefinition {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cost_center: Option<String>,
+    /// Regular expression to find in the relation name
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub relation: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub counter_iban: Option<String>,
+    /// Regular expression to find in the description
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// Regular expressions to find in attributes of the bank transaction
+    #[serde(default, skip_serializing_if = "IndexMap::is_empty")]
+    pub attributes: IndexMap<String, String>,
+    #[serde(flatten)]
+    pub amount: AmountCondition,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub weekdays: Vec<Weekday>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub from: Option<NaiveDate>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub until: Option<NaiveDate>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct AssignmentRuleDefinition {
+    #[serde(rename = "account")]
+    pub account_code: String,
+    /// Higher wins; between equal priorities the first rule wins
+    pub priority: i32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+    /// Prefix for the description of the bank transaction
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub splits: Vec<Split>,
+    #[serde(default)]
+    pub when: RuleConditionsDefinition,
+}
+
+/// What is known of a bank transaction when it is assigned
+pub struct RuleInput<'t> {
+    pub cost_center: &'t str,
+    pub relation_name: Option<&'t str>,
+    pub counter_iban: Option<&'t str>,
+    pub description: Option<&'t str>,
+    pub attributes: &'t HashMap<String, String>,
+    pub amount: Decimal,
+    pub date: NaiveDate,
+}
+
+/// Where an expression of a rule found its text
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct MatchedText {
+    /// `relation`, `description` or the name of an attribute
+    pub field: String,
+    pub text: String,
+    /// Byte offsets of the text within the field
+    pub start: usize,
+    pub end: usize,
+}
+
+/// The outcome of a matching rule
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RuleMatch {
+    /// The texts found by the relation, description and attribute expressions
+    pub matched: Vec<MatchedText>,
+}
+
+/// The rule that assigned a bank transaction, kept to explain the assignment
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct AssignedBy {
+    pub section: String,
+    pub key: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pattern: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub matched: Vec<MatchedText>,
+}
+
+#[derive(Debug, Default)]
+struct RuleConditions {
+    cost_center: Option<String>,
+    relation: Option<Regex>,
+    counter_iban: Option<String>,
+    description: Option<Regex>,
+    attributes: Vec<(String, Regex)>,
+    amount: AmountCondition,
+    weekdays: Vec<Weekday>,
+    from: Option<NaiveDate>,
+    until: Option<NaiveDate>,
+}
+
+/// An absent expression finds anything, other expressions only themselves
+fn includes_expression(expression: &Option<Regex>, other: &Option<Regex>) -> bool {
+    match (expression, other) {
+        (None, _) => true,
+        (Some(expression), Some(other)) => expression.as_str() == other.as_str(),
+        (Some(_), None) => false,
+    }
+}
+
+impl RuleConditions {
+    /// Every transaction that meets the other conditions also meets these; only
+    /// identical expressions are known to find the same texts
+    fn includes(&self, other: &Self) -> bool {
+        self.cost_center
+            .as_ref()
+            .is_none_or(|cost_center| other.cost_center.as_ref() == Some(cost_center))
+            && self.counter_iban.as_ref().is_none_or(|counter_iban| {
+                other
+                    .counter_iban
+                    .as_ref()
+                    .is_some_and(|other_iban| other_iban.eq_ignore_ascii_case(counter_iban))
+            })
+            && includes_expression(&self.relation, &other.relation)
+            && includes_expression(&self.description, &other.description)
+            && self.attributes.iter().all(|(name, expression)| {
+                other
+                    .attributes
+                    .iter()
+                    .any(|(other_name, other_expression)| {
+                        name == other_name && expression.as_str() == other_expression.as_str()
+                    })
+            })
+            && self.amount.includes(&other.amount)
+            && (self.weekdays.is_empty()
+                || !other.weekdays.is_empty()
+                    && other
+                        .weekdays
+                        .iter()
+                        .all(|weekday| self.weekdays.contains(weekday)))
+            && self
+                .from
+                .is_none_or(|from| other.from.is_some_and(|other_from| other_from >= from))
+            && self
+                .until
+                .is_none_or(|until| other.until.is_some_and(|other_until| other_until <= until))
+    }
+
+    /// The conditions in words, with the expressions as compiled
+    fn describe(&self) -> Vec<String> {
+        let mut descriptions = vec![];
+        if let Some(cost_center) = &self.cost_center {
+            descriptions.push(format!("cost center is {}", cost_center));
+        }
+        if let Some(relation) = &self.relation {
+            descriptions.push(format!("relation matches {}", relation.as_str()));
+        }
+        if let Some(counter_iban) = &self.counter_iban {
+            descriptions.push(format!("counter IBAN is {}", counter_iban));
+        }
+        if let Some(description) = &self.description {
+            descriptions.push(format!("description matches {}", description.as_str()));
+        }
+        for (name, expression) in self.attributes.iter() {
+            descriptions.push(format!("{} matches {}", name, expression.as_str()));
+        }
+        if let Some(exact_amount) = self.amount.exact_amount {
+            descriptions.push(format!("amount is {}", exact_amount));
+        }
+        if let Some(min_amount) = self.amount.min_amount {
+            descriptions.push(format!("amount at least {}", min_amount));
+        }
+        if let Some(max_amount) = self.amount.max_amount {
+            descriptions.push(format!("amount at most {}", max_amount));
+        }
+        match self.amount.direction {
+            Some(Direction::Debit) => descriptions.push("debit".to_string()),
+            Some(Direction::Credit) => descriptions.push("credit".to_string()),
+            None => {}
+        }
+        if !self.weekdays.is_empty() {
+            let weekdays: Vec<String> = self.weekdays.iter().map(Weekday::to_string).collect();
+            descriptions.push(format!("on {}", weekdays.join(", ")));
+        }
+        if let Some(from) = self.from {
+            descriptions.push(format!("from {}", from));
+        }
+        if let Some(until) = self.until {
+            descriptions.push(format!("until {}", until));
+        }
+
+        descriptions
+    }
+}
+
+/// A compiled rule as listed on the assignment rules page
+#[derive(Debug, Serialize)]
+pub struct AssignmentRuleHibernate {
+    pub section: String,
+    pub key: String,
+    pub priority: i32,
+    pub reason: AssignmentReason,
+    pub account_code: String,
+    pub description: Option<String>,
+    pub pattern: Option<String>,
+    pub note: Option<String>,
+    pub conditions: Vec<String>,
+    pub splits: Vec<Split>,
+}
+
+impl From<&AssignmentRule> for AssignmentRuleHibernate {
+    fn from(rule: &AssignmentRule) -> Self {
+        Self {
+            section: rule.section.to_string(),
+            key: rule.key.clone(),
+            priority: rule.priority,
+            reason: rule.reason.clone(),
+            account_code: rule.account_code.clone(),
+            description: rule.description.clone(),
+            pattern: rule.pattern.clone(),
+            note: rule.note.clone(),
+            conditions: rule.conditions.describe(),
+            splits: rule.splits.clone(),
+        }
+    }
+}
+
+/// A compiled rule from any of the assignment sections of ledger.yaml
+#[derive(Debug)]
+pub struct AssignmentRule {
+    pub reason: AssignmentReason,
+    /// `assignment_rules`, one of the `assign_by_*` sections or `tag`
+    pub section: &'static str,
+    /// Key within the section: the position in `assignment_rules`, the contract,
+    /// the cost center and relation or the account code
+    pub key: String,
+    /// The search text as written in ledger.yaml
+    pub pattern: Option<String>,
+    pub note: Option<String>,
+    pub priority: i32,
+    pub account_code: String,
+    pub description: Option<String>,
+    pub splits: Vec<Split>,
+    conditions: RuleConditions,
+}
+
+fn case_insensitive(expression: &str) -> crate::Result<Regex> {
+    Ok(Regex::new(format!("(?i){}", expression).as_str())?)
+}
+
+fn literal(text: &str) -> crate::Result<Regex> {
+    Ok(Regex::new(format!("^{}$", regex::escape(text)).as_str())?)
+}
+
+impl AssignmentRule {
+    pub fn from_definition(
+        index: usize,
+        definition: &AssignmentRuleDefinition,
+    ) -> crate::Result<Self> {
+        let key = (index + 1).to_string();
+        let source = format!("assignment_rules/{}", key);
+        let when = &definition.when;
+        when.amount.validate(&source)?;
+        if let (Some(from), Some(until)) = (when.from, when.until) {
+            if from > until {
+                return Err(Error::InvalidAssignmentRule {
+                    rule: source,
+                    reason: "from is after until".to_string(),
+                });
+            }
+        }
+
+        let conditions = RuleConditions {
+            cost_center: when.cost_center.clone(),
+            relation: when.relation.as_deref().map(case_insensitive).transpose()?,
+            counter_iban: when.counter_iban.clone(),
+            description: when
+                .description
+                .as_deref()
+                .map(case_insensitive)
+                .transpose()?,
+            attributes: when
+                .attributes
+                .iter()
+                .map(|(name, expression)| Ok((name.clone(), case_insensitive(expression)?)))
+                .collect::<crate::Result<_>>()?,
+            amount: when.amount.clone(),
+            weekdays: when.weekdays.clone(),
+            from: when.from,
+            until: when.until,
+        };
+
+        Ok(Self {
+            reason: AssignmentReason::Rule,
+            section: "assignment_rules",
+            key,
+            pattern: when
+                .relation
+                .as_ref()
+                .or(when.description.as_ref())
+                .or_else(|| when.attributes.values().next())
+                .cloned(),
+            note: definition.note.clone(),
+            priority: definition.priority,
+            account_code: definition.account_code.clone(),
+            description: definition.description.clone(),
+            splits: definition.splits.clone(),
+            conditions,
+        })
+    }
+
+    /// The key is the cost center, " & " and the relation name
+    pub fn by_description(
+        key: &str,
+        definition: &AssignByDescriptionDefinition,
+    ) -> crate::Result<Self> {
+        let (cost_center, relation_name) = key.split_once(" & ").unwrap_or((key, ""));
+        let source = format!("assign_by_description/{}", key);
+        definition.amount.validate(&source)?;
+        Ok(Self {
+            reason: AssignmentReason::Description,
+            section: "assign_by_description",
+            key: key.to_string(),
+            pattern: Some(definition.search_expression.clone()),
+            note: definition.note.clone(),
+            priority: DESCRIPTION_PRIORITY,
+            account_code: definition.account_code.clone(),
+            description: None,
+            splits: definition.splits.clone(),
+            conditions: RuleConditions {
+                cost_center: Some(cost_center.to_string()),
+                relation: Some(literal(relation_name)?),
+                description: Some(case_insensitive(&definition.search_expression)?),
+                amount: definition.amount.clone(),
+                ..Default::default()
+            },
+        })
+    }
+
+    pub fn by_contract(
+        contract_code: &str,
+        definition: &AssignByContractDefinition,
+        contract_attribute: &str,
+    ) -> crate::Result<Self> {
+        let source = format!("assign_by_contract/{}", contract_code);
+        definition.amount.validate(&source)?;
+        Ok(Self {
+            reason: AssignmentReason::Contract,
+            section: "assign_by_contract",
+            key: contract_code.to_string(),
+            pattern: None,
+            note: definition.note.clone(),
+            priority: CONTRACT_PRIORITY,
+            account_code: definition.account_code.clone(),
+            description: definition.description.clone(),
+            splits: definition.splits.clone(),
+            conditions: RuleConditions {
+                attributes: vec![(contract_attribute.to_string(), literal(contract_code)?)],
+                amount: definition.amount.clone(),
+                ..Default::default()
+            },
+        })
+    }
+
+    /// A tag in the bank description that is the code of the account
+    pub fn by_tag(account_code: &str, tag_attribute: &str) -> crate::Result<Self> {
+        Ok(Self {
+            reason: AssignmentReason::Reference,
+            section: "tag",
+            key: account_code.to_string(),
+            pattern: None,
+            note: None,
+            priority: TAG_PRIORITY,
+            account_code: account_code.to_string(),
+            description: None,
+            splits: vec![],
+            conditions: RuleConditions {
+                attributes: vec![(tag_attribute.to_string(), literal(account_code)?)],
+                ..Default::default()
+            },
+        })
+    }
+
+    /// `{naam}` in the search term stands for one or two words
+    pub fn by_name(account_code: &str, definition: &AssignByNameDefinition) -> crate::Result<Self> {
+        let expression = definition
+            .search_term()
+            .replace(r#"{naam}"#, r#"\w+(\s+\w+)?"#);
+        let source = format!("assign_by_name/{}", account_code);
+        let amount = definition.amount();
+        amount.validate(&source)?;
+        Ok(Self {
+            reason: AssignmentReason::RelationName,
+            section: "assign_by_name",
+            key: account_code.to_string(),
+            pattern: Some(definition.search_term().to_string()),
+            note: None,
+            priority: NAME_PRIORITY,
+            account_code: account_code.to_string(),
+            description: None,
+            splits: vec![],
+            conditions: RuleConditions {
+                relation: Some(case_insensitive(&expression)?),
+                amount,
+                ..Default::default()
+            },
+        })
+    }
+
+    /// Section and key, e.g. `assign_by_name/recreational`
+    pub fn source(&self) -> String {
+        format!("{}/{}", self.section, self.key)
+    }
+
+    pub fn assigned_by(&self, rule_match: &RuleMatch) -> AssignedBy {
+        AssignedBy {
+            section: self.section.to_string(),
+            key: self.key.clone(),
+            pattern: self.pattern.clone(),
+            note: self.note.clone(),
+            matched: rule_match.matched.clone(),
+        }
+    }
+
+    pub fn matches(&self, input: &RuleInput) -> Option<RuleMatch> {
+        let conditions = &self.conditions;
+        if let Some(cost_center) = &conditions.cost_center {
+            if cost_center != input.cost_center {
+                return None;
+            }
+        }
+        if let Some(counter_iban) = &conditions.counter_iban {
+            if !input
+                .counter_iban
+                .is_some_and(|iban| iban.eq_ignore_ascii_case(counter_iban))
+            {
+                return None;
+            }
+        }
+        if !conditions.amount.matches(input.amount)
+            || conditions.from.is_some_and(|from| input.date < from)
+            || conditions.until.is_some_and(|until| input.date > until)
+        {
+            return None;
+        }
+        if !conditions.weekdays.is_empty() && !conditions.weekdays.contains(&input.date.weekday()) {
+            return None;
+        }
+
+        let mut matched = vec![];
+        let mut find =
+            |field: &str, expression: Option<&Regex>, text: Option<&str>| match expression {
+                Some(expression) => {
+                    let found = expression.find(text?)?;
+                    matched.push(MatchedText {
+                        field: field.to_string(),
+                        text: found.as_str().to_string(),
+                        start: found.start(),
+                        end: found.end(),
+                    });
+                    Some(())
+                }
+                None => Some(()),
+            };
+        find(
+            "relation",
+            conditions.relation.as_ref(),
+            input.relation_name,
+        )?;
+        find(
+            "description",
+            conditions.description.as_ref(),
+            input.description,
+        )?;
+        for (name, expression) in conditions.attributes.iter() {
+            let text = input.attributes.get(name).map(String::as_str);
+            find(name, Some(expression), text)?;
+        }
+
+        Some(RuleMatch { matched })
+    }
+}
+
+/// Pairs of a rule and an earlier rule that matches whenever it matches, so it
+/// never wins under the `first` policy; the rules are in priority order
+pub fn shadowed_rules(rules: &[AssignmentRule]) -> Vec<(&AssignmentRule, &AssignmentRule)> {
+    rules
+        .iter()
+        .enumerate()
+        .filter_map(|(index, rule)| {
+            let shadowing = rules[..index]
+                .iter()
+                .find(|earlier| earlier.conditions.includes(&rule.conditions))?;
+            Some((rule, shadowing))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONTRACT: &str = "Machtiging ID";
+    const TAG: &str = "Tag";
+
+    fn input<'t>(
+        relation_name: &'t str,
+        description: &'t str,
+        attributes: &'t HashMap<String, String>,
+    ) -> RuleInput<'t> {
+        RuleInput {
+            cost_center: "Hers",
+            relation_name: Some(relation_name),
+            counter_iban: None,
+            description: Some(description),
+            attributes,
+            amount: Decimal::new(-1250, 2),
+            date: NaiveDate::from_ymd_opt(2022, 1, 3).unwrap(),
+        }
+    }
+
+    fn chosen<'r>(rules: &'r [AssignmentRule], input: &RuleInput) -> Option<&'r AssignmentRule> {
+        let matches: Vec<(&AssignmentRule, RuleMatch)> = rules
+            .iter()
+            .filter_map(|rule| Some((rule, rule.matches(input)?)))
+            .collect();
+        AssignmentPolicy::First
+            .choose(&matches)
+            .map(|(rule, _)| *rule)
+    }
+
+    fn rule(index: usize, yaml: &str) -> AssignmentRule {
+        let definition: AssignmentRuleDefinition = serde_yaml::from_str(yaml).unwrap();
+        AssignmentRule::from_definition(index, &definition).unwrap()
+    }
+
+    #[test]
+    fn equal_priorities_keep_their_order() {
+        let mut rules = vec![
+            rule(0, "{account: low, priority: 10, when: {relation: Eneco}}"),
+            rule(1, "{account: first, priority: 50, when: {relation: Eneco}}"),
+            rule(
+                2,
+                "{account: second, priority: 50, when: {relation: Eneco}}",
+            ),
+        ];
+        sort_by_priority(&mut rules);
+
+        let accounts: Vec<&str> = rules
+            .iter()
+            .map(|rule| rule.account_code.as_str())
+            .collect();
+        assert_eq!(accounts, vec!["first", "second", "low"]);
+
+        let attributes = HashMap::new();
+        let chosen = chosen(&rules, &input("Eneco", "", &attributes)).unwrap();
+        assert_eq!(chosen.account_code, "first");
+    }
+
+    #[test]
+    fn sections_keep_the_baseline_precedence() {
+        let description: AssignByDescriptionDefinition =
+            serde_yaml::from_str("{account: by_description, search: energie}").unwrap();
+        let contract: AssignByContractDefinition =
+            serde_yaml::from_str("{account: by_contract}").unwrap();
+        let name = AssignByNameDefinition::Search("eneco".to_string());
+
+        let mut rules = vec![
+            AssignmentRule::by_name("by_name", &name).unwrap(),
+            AssignmentRule::by_tag("by_tag", TAG).unwrap(),
+            AssignmentRule::by_contract("MND123", &contract, CONTRACT).unwrap(),
+            AssignmentRule::by_description("Hers & Eneco", &description).unwrap(),
+        ];
+        sort_by_priority(&mut rules);
+
+        let attributes = HashMap::from([
+            (CONTRACT.to_string(), "MND123".to_string()),
+            (TAG.to_string(), "by_tag".to_string()),
+        ]);
+        let all = input("Eneco", "Energie jan", &attributes);
+        assert_eq!(chosen(&rules, &all).unwrap().account_code, "by_description");
+
+        let no_description = input("Eneco", "", &attributes);
+        assert_eq!(
+            chosen(&rules, &no_description).unwrap().account_code,
+            "by_contract"
+        );
+
+        let tag_only = HashMap::from([(TAG.to_string(), "by_tag".to_string())]);
+        let no_contract = input("Eneco", "", &tag_only);
+        assert_eq!(chosen(&rules, &no_contract).unwrap().account_code, "by_tag");
+
+        let none = HashMap::new();
+        let name_only = input("Eneco", "", &none);
+        assert_eq!(chosen(&rules, &name_only).unwrap().account_code, "by_name");
+    }
+
+    #[test]
+    fn naam_finds_the_full_relation_name() {
+        let name = AssignByNameDefinition::Search("Restaurant {naam}".to_string());
+        let rule = AssignmentRule::by_name("recreational", &name).unwrap();
+
+        let attributes = HashMap::new();
+        let rule_match = rule
+            .matches(&input("Restaurant De Kas", "", &attributes))
+            .unwrap();
+        assert_eq!(
+            rule_match.matched,
+            vec![MatchedText {
+                field: "relation".to_string(),
+                text: "Restaurant De Kas".to_string(),
+                start: 0,
+                end: 17,
+            }]
+        );
+    }
+}
@@ -7,4 +7,8 @@ pub struct BankAccount {
     #[serde(rename = "cost_center")]
     pub cost_center_code: String,
     pub description: String,
+    /// Asset account the transactions of this bank account are posted on;
+    /// when not configured, an account with the IBAN as code is used
+    #[serde(default, rename = "account", skip_serializing_if = "Option::is_none")]
+    pub account_code: Option<String>,
 }
@@ -1,7 +1,11 @@
 use serde::{Deserialize, Serialize};
 
-#[derive(Serialize, Deserialize, Debug)]
+use crate::CsvMapping;
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct BankFormat {
     pub name: String,
     pub description: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub csv_mapping: Option<CsvMapping>,
 }
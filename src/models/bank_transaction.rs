@@ -2,21 +2,28 @@ use std::collections::HashMap;
 
 use chrono::NaiveDate;
 use rust_decimal::Decimal;
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 
-#[derive(Serialize, Debug)]
+use crate::{AssignedBy, Split};
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
 #[serde(rename_all = "snake_case")]
 pub enum AssignmentReason {
     Reference,
     RelationName,
     Contract,
     Description,
+    /// A rule of the `assignment_rules` section
+    Rule,
+    Manual,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 #[serde(rename = "camelCase")]
 pub struct BankTransaction {
     pub id: String,
+    #[serde(default)]
+    pub iban: String,
     pub date: NaiveDate,
 
     pub cost_center: String,
@@ -25,7 +32,70 @@ pub struct BankTransaction {
 
     pub attributes: HashMap<String, String>,
     pub amount: Decimal,
+    #[serde(default)]
+    pub balance_before: Option<Decimal>,
+    #[serde(default)]
+    pub balance_after: Option<Decimal>,
 
     pub account_code: Option<String>,
     pub assignment_reason: Option<AssignmentReason>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub assigned_by: Option<AssignedBy>,
+    /// Matching assignment rules that disagree on the account
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub assignment_warning: Option<String>,
+    /// Shares of the amount posted on other accounts than `account_code`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub splits: Vec<Split>,
+}
+
+impl BankTransaction {
+    /// Attributes the assignment consumes or rewrites, so they differ between imports of the same
+    /// bank data
+    const ASSIGNED_ATTRIBUTES: [&'static str; 4] = [
+        crate::ing::DescriptionProperties::NAME,
+        crate::ing::DescriptionProperties::TAG,
+        crate::ing::DescriptionProperties::CONTRACT,
+        crate::ing::DescriptionProperties::DESCRIPTION,
+    ];
+
+    /// Whether both carry the same data from the bank, whatever account they were assigned to
+    pub fn same_bank_data(&self, other: &BankTransaction) -> bool {
+        let bank_attributes = |transaction: &BankTransaction| -> HashMap<String, String> {
+            transaction
+                .attributes
+                .iter()
+                .filter(|(key, _)| !Self::ASSIGNED_ATTRIBUTES.contains(&key.as_str()))
+                .map(|(key, value)| (key.clone(), value.clone()))
+                .collect()
+        };
+        // Only known relations keep their IBAN
+        let same_counter_iban = match (&self.relation_iban, &other.relation_iban) {
+            (Some(one), Some(other)) => one == other,
+            _ => true,
+        };
+
+        self.iban == other.iban
+            && self.date == other.date
+            && self.amount == other.amount
+            && self.balance_before == other.balance_before
+            && self.balance_after == other.balance_after
+            && same_counter_iban
+            && bank_attributes(self) == bank_attributes(other)
+    }
+
+    /// Relation and description, as registered in the journal
+    pub fn journal_description(&self) -> String {
+        let description = self
+            .attributes
+            .get(crate::ing::DescriptionProperties::DESCRIPTION);
+        match (&self.relation_name, description) {
+            (Some(relation_name), Some(description)) => {
+                format!("{} {}", relation_name, description)
+            }
+            (Some(relation_name), None) => relation_name.clone(),
+            (None, Some(description)) => description.clone(),
+            (None, None) => String::new(),
+        }
+    }
 }
@@ -0,0 +1,65 @@
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+
+use crate::{Error, Period};
+
+/// Budget of an account (including its descendants), optionally for a single
+/// cost center; exactly one of the amounts must be given
+///
+/// The amounts are positive in the direction of the normal balance of the account,
+/// i.e. spending for expense accounts and earnings for income accounts.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Budget {
+    #[serde(rename = "account")]
+    pub account_code: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cost_center: Option<String>,
+    /// Spread evenly over the months
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub yearly: Option<Decimal>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub monthly: Option<Decimal>,
+    /// Twelve amounts, January first
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub per_month: Option<Vec<Decimal>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+}
+
+impl Budget {
+    /// Checks the amounts; the account and cost center are checked by the ledger
+    pub fn validate(&self) -> crate::Result<()> {
+        let invalid = |reason: &str| {
+            Err(Error::InvalidBudget {
+                account_code: self.account_code.clone(),
+                reason: reason.to_string(),
+            })
+        };
+
+        match (&self.yearly, &self.monthly, &self.per_month) {
+            (Some(_), None, None) | (None, Some(_), None) => Ok(()),
+            (None, None, Some(per_month)) if per_month.len() == 12 => Ok(()),
+            (None, None, Some(_)) => invalid("per_month needs an amount for each of the 12 months"),
+            _ => invalid("give exactly one of yearly, monthly or per_month"),
+        }
+    }
+
+    pub fn month_amount(&self, month: u32) -> Decimal {
+        match (&self.yearly, &self.monthly, &self.per_month) {
+            (Some(yearly), _, _) => yearly / Decimal::from(12),
+            (None, Some(monthly), _) => *monthly,
+            (None, None, Some(per_month)) => per_month
+                .get(month as usize - 1)
+                .copied()
+                .unwrap_or_default(),
+            (None, None, None) => Decimal::ZERO,
+        }
+    }
+
+    pub fn amount(&self, period: &Period) -> Decimal {
+        period
+            .as_range()
+            .map(|month| self.month_amount(month))
+            .sum()
+    }
+}
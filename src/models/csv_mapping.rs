@@ -0,0 +1,87 @@
+use std::fmt::Display;
+
+use serde::{Deserialize, Serialize};
+
+/// A column of a CSV file, either by its header name or by its (zero based) position
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(untagged)]
+pub enum CsvColumn {
+    Index(usize),
+    Name(String),
+}
+
+/// How to tell money going out of the account from money coming in
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "snake_case")]
+pub enum CsvSignConvention {
+    /// The amount itself carries a sign; negative is money going out
+    Signed,
+    /// The amount is always positive, a separate column tells the direction
+    DebitCreditColumn {
+        column: CsvColumn,
+        debit: Vec<String>,
+        credit: Vec<String>,
+    },
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct CsvColumns {
+    pub account: CsvColumn,
+    pub date: CsvColumn,
+    pub amount: CsvColumn,
+    pub balance_after: Option<CsvColumn>,
+    pub counter_iban: Option<CsvColumn>,
+    pub name: Option<CsvColumn>,
+    /// Joined with a space into the description of the transaction
+    #[serde(default)]
+    pub description: Vec<CsvColumn>,
+    /// Free text with embedded `Name: value` properties
+    pub info: Option<CsvColumn>,
+    pub contract: Option<CsvColumn>,
+    pub reference: Option<CsvColumn>,
+    pub tag: Option<CsvColumn>,
+    /// Columns that identify a transaction; all columns when empty
+    #[serde(default)]
+    pub id: Vec<CsvColumn>,
+}
+
+/// Declarative description of a bank's CSV export
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct CsvMapping {
+    #[serde(default = "default_delimiter")]
+    pub delimiter: char,
+    #[serde(default = "default_quote")]
+    pub quote: char,
+    #[serde(default = "default_has_headers")]
+    pub has_headers: bool,
+    pub date_format: String,
+    #[serde(default = "default_decimal_separator")]
+    pub decimal_separator: char,
+    pub sign: CsvSignConvention,
+    pub columns: CsvColumns,
+}
+
+fn default_delimiter() -> char {
+    ','
+}
+
+fn default_quote() -> char {
+    '"'
+}
+
+fn default_has_headers() -> bool {
+    true
+}
+
+fn default_decimal_separator() -> char {
+    '.'
+}
+
+impl Display for CsvColumn {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CsvColumn::Index(index) => write!(f, "#{}", index),
+            CsvColumn::Name(name) => f.write_str(name),
+        }
+    }
+}
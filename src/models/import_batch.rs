@@ -0,0 +1,40 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::{BankTransaction, JournalEntry};
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ImportBatchStatus {
+    Staged,
+    Committed,
+    RolledBack,
+}
+
+/// The transactions of a single uploaded file, which are staged for review
+/// before they are committed into the journal as a unit
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ImportBatch {
+    pub id: String,
+    pub uploader: String,
+    pub file_name: String,
+    pub format_code: String,
+    pub timestamp: DateTime<Utc>,
+    /// Hex encoded SHA-256 of the uploaded file
+    pub checksum: String,
+    pub status: ImportBatchStatus,
+    /// When the batch was committed; a later committed batch may depend on its transactions
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub committed_at: Option<DateTime<Utc>>,
+    /// The uploaded transactions, as assigned at the time of the upload
+    pub transactions: Vec<BankTransaction>,
+    /// The transactions that were imported for the first time by committing this batch
+    #[serde(default)]
+    pub added_transaction_ids: Vec<String>,
+    /// The stored versions of the transactions that committing this batch changed
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub replaced_transactions: Vec<BankTransaction>,
+    /// The journal entries of the changed transactions, which committing this batch re-posted
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub replaced_journal_entries: Vec<JournalEntry>,
+}
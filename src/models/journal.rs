@@ -1,12 +1,19 @@
 use chrono::NaiveDate;
 use rusty_money::{iso::Currency, Money};
 
+use crate::JournalEntry;
+
 pub trait Journal {
+    /// Adds a balanced entry to the journal and returns its id
+    fn register(&mut self, entry: JournalEntry) -> crate::Result<String>;
+
+    /// Registers the amount on one account, with the counter posting on another
     fn register_single(
         &mut self,
         date: NaiveDate,
         account_code: &str,
+        counter_account_code: &str,
         amount: &Money<Currency>,
         description: &str,
-    ) -> crate::Result<()>;
+    ) -> crate::Result<String>;
 }
@@ -2,11 +2,63 @@ use chrono::NaiveDate;
 use rust_decimal::{serde::float as AsFloat, Decimal};
 use serde::{Deserialize, Serialize};
 
-#[derive(Serialize, Deserialize, Debug)]
+/// A single account line of a journal entry; debit amounts are positive,
+/// credit amounts negative
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Posting {
+    pub account_code: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cost_center: Option<String>,
+    #[serde(with = "AsFloat")]
+    pub amount: Decimal,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct JournalEntry {
+    pub id: String,
+    pub date: NaiveDate,
+    pub description: String,
+    /// The relation of the bank transaction, as far as known
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub payee: Option<String>,
+    pub postings: Vec<Posting>,
+    /// The import batch that created this entry
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub batch_id: Option<String>,
+    /// The bank transaction that this entry registers
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bank_transaction_id: Option<String>,
+}
+
+/// An entry as written before entries had postings: the amount on a single
+/// account, against the bank account
+#[derive(Deserialize, Debug)]
+pub struct LegacyJournalEntry {
     pub date: NaiveDate,
     pub account_code: String,
     #[serde(with = "AsFloat")]
     pub amount: Decimal,
     pub description: String,
+    /// The bank account, needed only when the ledger has more than one
+    #[serde(default)]
+    pub iban: Option<String>,
+}
+
+/// An entry of journal.yaml, in the current or the legacy shape
+#[derive(Deserialize, Debug)]
+#[serde(untagged)]
+pub enum StoredJournalEntry {
+    Entry(JournalEntry),
+    Legacy(LegacyJournalEntry),
+}
+
+impl JournalEntry {
+    /// Sum of the postings, which is zero for a valid entry
+    pub fn balance(&self) -> Decimal {
+        self.postings.iter().map(|posting| posting.amount).sum()
+    }
+
+    pub fn is_balanced(&self) -> bool {
+        self.postings.len() >= 2 && self.balance().is_zero()
+    }
 }
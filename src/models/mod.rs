@@ -4,8 +4,8 @@ pub use bank_transaction::*;
 mod account;
 pub use account::*;
 
-mod assign_by_name_search;
-pub use assign_by_name_search::*;
+mod assign_by_name_definition;
+pub use assign_by_name_definition::*;
 
 mod assign_by_contract_definition;
 pub use assign_by_contract_definition::*;
@@ -13,6 +13,9 @@ pub use assign_by_contract_definition::*;
 mod assign_by_description;
 pub use assign_by_description::*;
 
+mod assignment_rule;
+pub use assignment_rule::*;
+
 mod bank_account;
 pub use bank_account::*;
 
@@ -22,6 +25,18 @@ pub use cost_center::*;
 mod bank_format;
 pub use bank_format::*;
 
+mod csv_mapping;
+pub use csv_mapping::*;
+
+mod import_batch;
+pub use import_batch::*;
+
+mod split;
+pub use split::*;
+
+mod budget;
+pub use budget::*;
+
 mod period;
 pub use period::*;
 
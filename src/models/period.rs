@@ -1,7 +1,10 @@
-use std::ops::RangeInclusive;
+use std::{fmt::Display, ops::RangeInclusive};
 
 use chrono::{Datelike, NaiveDate};
 
+use crate::Error;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Period {
     Month(u32),
     Months(u32, u32),
@@ -47,9 +50,78 @@ impl Period {
         }
     }
 
-    pub fn contains(&self, d: NaiveDate) -> bool {
+    /// Whether the date falls in the period of the given (ledger) year
+    pub fn contains(&self, year: u32, d: NaiveDate) -> bool {
         let month = d.month();
         let rng = self.as_range();
-        rng.contains(&month)
+        d.year() == year as i32 && rng.contains(&month)
+    }
+
+    /// Whether the date is before the start of the period of the given year
+    pub fn starts_after(&self, year: u32, d: NaiveDate) -> bool {
+        (d.year(), d.month()) < (year as i32, self.first_month())
+    }
+
+    /// Parses the `period` and `number` query parameters of reports,
+    /// e.g. `month` and `3`, `quarter` and `2` or just `year`
+    pub fn parse(kind: &str, number: Option<u32>) -> crate::Result<Self> {
+        let unrecognised = || match number {
+            Some(number) => Error::UnrecognisedPeriod(format!("{} {}", kind, number)),
+            None => Error::UnrecognisedPeriod(kind.to_string()),
+        };
+        let period = match (kind.to_lowercase().as_str(), number) {
+            ("month", Some(month @ 1..=12)) => Period::Month(month),
+            ("quarter", Some(quarter @ 1..=4)) => Period::Quarter(quarter),
+            ("halfyear", Some(halfyear @ 1..=2)) => Period::Halfyear(halfyear),
+            ("year", _) => Period::Year,
+            _ => return Err(unrecognised()),
+        };
+
+        Ok(period)
+    }
+
+    /// The first month of the period
+    pub fn first_month(&self) -> u32 {
+        *self.as_range().start()
+    }
+}
+
+impl Display for Period {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Period::Month(m) => write!(f, "month {}", m),
+            Period::Months(from, until) if from == until => write!(f, "month {}", from),
+            Period::Months(from, until) => write!(f, "months {} - {}", from, until),
+            Period::Quarter(q) => write!(f, "quarter {}", q),
+            Period::Halfyear(hy) => write!(f, "halfyear {}", hy),
+            Period::Year => write!(f, "year"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(year: i32, month: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, 15).unwrap()
+    }
+
+    #[test]
+    fn only_contains_dates_of_the_year() {
+        let quarter = Period::Quarter(2);
+        assert!(quarter.contains(2022, date(2022, 4)));
+        assert!(!quarter.contains(2022, date(2022, 7)));
+        assert!(!quarter.contains(2022, date(2021, 4)));
+        assert!(!Period::Year.contains(2022, date(2023, 1)));
+    }
+
+    #[test]
+    fn earlier_years_are_before_the_period() {
+        let quarter = Period::Quarter(2);
+        assert!(quarter.starts_after(2022, date(2022, 3)));
+        assert!(quarter.starts_after(2022, date(2021, 11)));
+        assert!(!quarter.starts_after(2022, date(2022, 4)));
+        assert!(!Period::Month(1).starts_after(2022, date(2023, 1)));
     }
 }
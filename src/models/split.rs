@@ -0,0 +1,288 @@
+use std::{fmt::Display, str::FromStr};
+
+use rust_decimal::{Decimal, RoundingStrategy};
+use serde::{Deserialize, Serialize};
+
+use crate::Error;
+
+/// Part of a bank transaction that is posted on a separate account
+///
+/// A split has either a fixed amount, a percentage of the transaction amount, or
+/// neither: then it gets the remainder. Fixed amounts are given without sign, they
+/// take the sign of the transaction.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Split {
+    #[serde(rename = "account")]
+    pub account_code: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub amount: Option<Decimal>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub percentage: Option<Decimal>,
+}
+
+impl Split {
+    pub fn is_remainder(&self) -> bool {
+        self.amount.is_none() && self.percentage.is_none()
+    }
+}
+
+/// Checks the splits on their own; the accounts are checked by the ledger
+pub fn validate_splits(splits: &[Split]) -> crate::Result<()> {
+    let invalid = |reason: String| Err(Error::InvalidSplit(reason));
+
+    let mut remainders = 0;
+    let mut percentages = Decimal::ZERO;
+    for split in splits {
+        match (split.amount, split.percentage) {
+            (Some(_), Some(_)) => {
+                return invalid(format!(
+                    "'{}' has both an amount and a percentage",
+                    split.account_code
+                ))
+            }
+            (Some(amount), None) if amount <= Decimal::ZERO => {
+                return invalid(format!(
+                    "'{}' must have a positive amount",
+                    split.account_code
+                ))
+            }
+            (None, Some(percentage))
+                if percentage <= Decimal::ZERO || percentage > Decimal::ONE_HUNDRED =>
+            {
+                return invalid(format!(
+                    "'{}' must have a percentage between 0 and 100",
+                    split.account_code
+                ))
+            }
+            (None, Some(percentage)) => percentages += percentage,
+            (None, None) => remainders += 1,
+            _ => {}
+        }
+    }
+
+    if remainders > 1 {
+        return invalid("only one split can take the remainder".to_string());
+    }
+    if percentages > Decimal::ONE_HUNDRED {
+        return invalid(format!("the percentages add up to {}", percentages));
+    }
+
+    Ok(())
+}
+
+/// Divides the amount over the splits, in minor units of the currency (`exponent`
+/// decimals), such that the parts add up to exactly the (rounded) amount
+///
+/// Rounding differences of the percentages go to the splits with the largest
+/// rounded off fractions. What is left after the fixed amounts and percentages goes to
+/// the remainder split, or else to `rest_account_code`. Parts of the same account are
+/// added up, parts of zero are left out.
+pub fn allocate_splits(
+    amount: Decimal,
+    splits: &[Split],
+    rest_account_code: &str,
+    exponent: u32,
+) -> crate::Result<Vec<(String, Decimal)>> {
+    validate_splits(splits)?;
+
+    let unit = Decimal::new(1, exponent);
+    let amount = amount.round_dp_with_strategy(exponent, RoundingStrategy::MidpointAwayFromZero);
+    let sign = if amount.is_sign_negative() {
+        -Decimal::ONE
+    } else {
+        Decimal::ONE
+    };
+
+    let mut parts: Vec<Decimal> = vec![Decimal::ZERO; splits.len()];
+    let mut fractions: Vec<(usize, Decimal)> = vec![];
+    let mut exact_percentages = Decimal::ZERO;
+    for (index, split) in splits.iter().enumerate() {
+        if let Some(fixed) = split.amount {
+            parts[index] = sign
+                * fixed.round_dp_with_strategy(exponent, RoundingStrategy::MidpointAwayFromZero);
+        } else if let Some(percentage) = split.percentage {
+            let exact = amount * percentage / Decimal::ONE_HUNDRED;
+            parts[index] = exact.round_dp_with_strategy(exponent, RoundingStrategy::ToZero);
+            fractions.push((index, (exact - parts[index]).abs()));
+            exact_percentages += exact;
+        }
+    }
+
+    // Largest remainder method, so the percentages add up to their rounded total
+    let percentages_total =
+        exact_percentages.round_dp_with_strategy(exponent, RoundingStrategy::MidpointAwayFromZero);
+    let allocated: Decimal = fractions.iter().map(|(index, _)| parts[*index]).sum();
+    let mut units_left = ((percentages_total - allocated) / unit).abs();
+    fractions.sort_by(|one, other| other.1.cmp(&one.1).then(one.0.cmp(&other.0)));
+    for (index, _) in fractions.iter() {
+        if units_left <= Decimal::ZERO {
+            break;
+        }
+        parts[*index] += sign * unit;
+        units_left -= Decimal::ONE;
+    }
+
+    let remainder = amount - parts.iter().sum::<Decimal>();
+    if !remainder.is_zero() && remainder.is_sign_negative() != amount.is_sign_negative() {
+        return Err(Error::InvalidSplit(format!(
+            "the splits add up to more than {}",
+            amount
+        )));
+    }
+
+    let mut result: Vec<(String, Decimal)> = vec![];
+    let mut add = |account_code: &str, part: Decimal| match result
+        .iter_mut()
+        .find(|(code, _)| code == account_code)
+    {
+        Some((_, total)) => *total += part,
+        None => result.push((account_code.to_string(), part)),
+    };
+    let mut remainder_allocated = false;
+    for (split, part) in splits.iter().zip(parts) {
+        if split.is_remainder() {
+            add(&split.account_code, remainder);
+            remainder_allocated = true;
+        } else {
+            add(&split.account_code, part);
+        }
+    }
+    if !remainder_allocated {
+        add(rest_account_code, remainder);
+    }
+
+    result.retain(|(_, part)| !part.is_zero());
+    if result.is_empty() {
+        result.push((rest_account_code.to_string(), Decimal::ZERO));
+    }
+
+    Ok(result)
+}
+
+impl Display for Split {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match (self.amount, self.percentage) {
+            (Some(amount), _) => write!(f, "{} {}", self.account_code, amount),
+            (None, Some(percentage)) => write!(f, "{} {}%", self.account_code, percentage),
+            (None, None) => write!(f, "{} *", self.account_code),
+        }
+    }
+}
+
+/// Parses `<account> <amount>`, `<account> <percentage>%` or `<account> *`
+/// (or just the account) for the remainder
+impl FromStr for Split {
+    type Err = Error;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let mut words = text.split_whitespace();
+        let account_code = match words.next() {
+            Some(account_code) => account_code.to_string(),
+            None => return Err(Error::InvalidSplit("missing account".to_string())),
+        };
+        let share = words.next();
+        if words.next().is_some() {
+            return Err(Error::InvalidSplit(format!(
+                "'{}' has too many parts",
+                text
+            )));
+        }
+
+        let invalid = || Error::InvalidSplit(format!("'{}' is not a valid share", text));
+        let (amount, percentage) = match share {
+            None | Some("*") => (None, None),
+            Some(share) => match share.strip_suffix('%') {
+                Some(percentage) => (
+                    None,
+                    Some(Decimal::from_str_exact(percentage).map_err(|_| invalid())?),
+                ),
+                None => (
+                    Some(Decimal::from_str_exact(share).map_err(|_| invalid())?),
+                    None,
+                ),
+            },
+        };
+
+        Ok(Self {
+            account_code,
+            amount,
+            percentage,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn splits(texts: &[&str]) -> Vec<Split> {
+        texts.iter().map(|text| text.parse().unwrap()).collect()
+    }
+
+    fn allocate(amount: Decimal, texts: &[&str]) -> Vec<(String, Decimal)> {
+        allocate_splits(amount, &splits(texts), "rest", 2).unwrap()
+    }
+
+    fn part(code: &str, amount: i64) -> (String, Decimal) {
+        (code.to_string(), Decimal::new(amount, 2))
+    }
+
+    #[test]
+    fn largest_remainder_gets_the_rounding_difference() {
+        assert_eq!(
+            allocate(Decimal::new(-1001, 2), &["a 33%", "b 33%", "c 34%"]),
+            vec![part("a", -330), part("b", -330), part("c", -341)]
+        );
+        // Equal fractions: the first split gets the unit
+        assert_eq!(
+            allocate(Decimal::new(1, 2), &["a 50%", "b 50%"]),
+            vec![part("a", 1)]
+        );
+    }
+
+    #[test]
+    fn fixed_amounts_take_the_sign_and_the_rest_goes_to_the_remainder() {
+        assert_eq!(
+            allocate(Decimal::new(-10000, 2), &["a 12.50", "b 10%", "c *"]),
+            vec![part("a", -1250), part("b", -1000), part("c", -7750)]
+        );
+        assert_eq!(
+            allocate(Decimal::new(10000, 2), &["a 33.33%", "b 33.33%"]),
+            vec![part("a", 3333), part("b", 3333), part("rest", 3334)]
+        );
+    }
+
+    #[test]
+    fn parts_always_add_up_to_the_amount() {
+        let combinations: [&[&str]; 4] = [
+            &["a 33.3333%", "b 33.3333%", "c 33.3334%"],
+            &["a 1%", "b 2%", "c 97%"],
+            &["a 14.2857%", "b 28.5714%", "c *"],
+            &["a 0.01", "b 49.99%", "c 50%"],
+        ];
+        for cents in (-1003..=1003).step_by(7) {
+            let amount = Decimal::new(cents, 2);
+            for texts in combinations {
+                let parts = match allocate_splits(amount, &splits(texts), "rest", 2) {
+                    Ok(parts) => parts,
+                    // A fixed amount larger than the transaction
+                    Err(_) => continue,
+                };
+                let total: Decimal = parts.iter().map(|(_, part)| *part).sum();
+                assert_eq!(total, amount, "{:?} of {}", texts, amount);
+            }
+        }
+    }
+
+    #[test]
+    fn splits_over_the_amount_are_rejected() {
+        assert!(allocate_splits(Decimal::new(1000, 2), &splits(&["a 12"]), "rest", 2).is_err());
+        assert!(allocate_splits(
+            Decimal::new(1000, 2),
+            &splits(&["a 60%", "b 60%"]),
+            "rest",
+            2
+        )
+        .is_err());
+    }
+}
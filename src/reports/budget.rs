@@ -0,0 +1,192 @@
+use chrono::Datelike;
+use rust_decimal::Decimal;
+use serde::Serialize;
+
+use crate::{
+    AccountType, Budget, CostCentersRepository, Error, JournalEntry, Ledger, Period, Posting,
+};
+
+use super::round_amount;
+
+#[derive(Serialize, Debug)]
+pub struct BudgetLine {
+    pub account_code: String,
+    pub description: String,
+    pub cost_center: Option<String>,
+    pub note: Option<String>,
+    pub budget: Decimal,
+    pub actual: Decimal,
+    pub remaining: Decimal,
+    /// `None` when nothing is budgeted for the period
+    pub percent_used: Option<Decimal>,
+    pub over_budget: bool,
+}
+
+/// Actual amounts of the journal against the budgets over a period
+#[derive(Serialize, Debug)]
+pub struct BudgetReport {
+    pub period: String,
+    pub cost_center: Option<String>,
+    pub lines: Vec<BudgetLine>,
+}
+
+impl BudgetReport {
+    /// With a cost center only the budgets of that cost center are shown
+    pub fn new(ledger: &Ledger, period: Period, cost_center: Option<&str>) -> crate::Result<Self> {
+        if let Some(cost_center) = cost_center {
+            if !ledger.cost_centers().contains(&cost_center) {
+                return Err(Error::UnrecognisedCostCenterCode(cost_center.to_string()));
+            }
+        }
+
+        let exponent = ledger.currency().exponent;
+        let lines = ledger
+            .budgets()
+            .iter()
+            .filter(|budget| cost_center.is_none() || budget.cost_center.as_deref() == cost_center)
+            .map(|budget| {
+                let budgeted = round_amount(budget.amount(&period), exponent);
+                let actual = round_amount(
+                    actual_amount(ledger, budget, &period, ledger.journal.iter()),
+                    exponent,
+                );
+                BudgetLine {
+                    account_code: budget.account_code.clone(),
+                    description: account_description(ledger, budget),
+                    cost_center: budget.cost_center.clone(),
+                    note: budget.note.clone(),
+                    budget: budgeted,
+                    actual,
+                    remaining: budgeted - actual,
+                    percent_used: (!budgeted.is_zero())
+                        .then(|| (actual * Decimal::ONE_HUNDRED / budgeted).round_dp(1)),
+                    over_budget: is_spending(ledger, budget) && actual > budgeted,
+                }
+            })
+            .collect();
+
+        Ok(Self {
+            period: period.to_string(),
+            cost_center: cost_center.map(str::to_string),
+            lines,
+        })
+    }
+}
+
+/// A spending budget that the entries of an upload pushed over, for the year to
+/// date up to the last month of those entries
+#[derive(Serialize, Debug)]
+pub struct BudgetOverrun {
+    pub account_code: String,
+    pub description: String,
+    pub cost_center: Option<String>,
+    pub period: String,
+    pub budget: Decimal,
+    pub actual_before: Decimal,
+    pub actual: Decimal,
+}
+
+pub fn budget_overruns(ledger: &Ledger, new_entries: &[JournalEntry]) -> Vec<BudgetOverrun> {
+    let exponent = ledger.currency().exponent;
+    ledger
+        .budgets()
+        .iter()
+        .filter(|budget| is_spending(ledger, budget))
+        .filter_map(|budget| {
+            let last_month = new_entries
+                .iter()
+                .filter(|entry| {
+                    entry
+                        .postings
+                        .iter()
+                        .any(|posting| is_budgeted(ledger, budget, posting))
+                })
+                .map(|entry| entry.date.month())
+                .max()?;
+            let period = Period::Months(1, last_month);
+
+            let budgeted = round_amount(budget.amount(&period), exponent);
+            let actual_before = round_amount(
+                actual_amount(ledger, budget, &period, ledger.journal.iter()),
+                exponent,
+            );
+            let actual = actual_before
+                + round_amount(
+                    actual_amount(ledger, budget, &period, new_entries.iter()),
+                    exponent,
+                );
+            if actual_before > budgeted || actual <= budgeted {
+                return None;
+            }
+
+            Some(BudgetOverrun {
+                account_code: budget.account_code.clone(),
+                description: account_description(ledger, budget),
+                cost_center: budget.cost_center.clone(),
+                period: period.to_string(),
+                budget: budgeted,
+                actual_before,
+                actual,
+            })
+        })
+        .collect()
+}
+
+/// Income budgets are targets rather than limits, so they can not be overrun
+fn is_spending(ledger: &Ledger, budget: &Budget) -> bool {
+    ledger
+        .accounts
+        .get(&budget.account_code)
+        .map(|account| account.account_type != Some(AccountType::Income))
+        .unwrap_or(true)
+}
+
+fn account_description(ledger: &Ledger, budget: &Budget) -> String {
+    ledger
+        .accounts
+        .get(&budget.account_code)
+        .map(|account| account.description.clone())
+        .unwrap_or_default()
+}
+
+/// Sum of the budgeted postings in the period, as seen from the normal balance
+/// of the budgeted account
+fn actual_amount<'e>(
+    ledger: &Ledger,
+    budget: &Budget,
+    period: &Period,
+    entries: impl Iterator<Item = &'e JournalEntry>,
+) -> Decimal {
+    let balance: Decimal = entries
+        .filter(|entry| period.contains(ledger.year(), entry.date))
+        .flat_map(|entry| entry.postings.iter())
+        .filter(|posting| is_budgeted(ledger, budget, posting))
+        .map(|posting| posting.amount)
+        .sum();
+
+    match ledger.accounts.get(&budget.account_code) {
+        Some(account) => account.normal_balance().presented(balance),
+        None => balance,
+    }
+}
+
+/// Whether the posting is on the budgeted account or one of its descendants,
+/// and on the cost center of the budget
+fn is_budgeted(ledger: &Ledger, budget: &Budget, posting: &Posting) -> bool {
+    if budget.cost_center.is_some() && posting.cost_center != budget.cost_center {
+        return false;
+    }
+
+    let mut current = ledger.accounts.get(&posting.account_code);
+    while let Some(account) = current {
+        if account.code == budget.account_code {
+            return true;
+        }
+        current = account
+            .parent_code
+            .as_ref()
+            .and_then(|parent_code| ledger.accounts.get(parent_code));
+    }
+
+    false
+}
@@ -0,0 +1,176 @@
+use chrono::{Datelike, NaiveDate};
+use rust_decimal::{prelude::ToPrimitive, Decimal};
+
+use crate::{
+    AccountsRepository, BankTransactionsRepository, CostCentersRepository, Error, IncomeStatements,
+    Ledger, Period,
+};
+
+use super::{
+    month_labels,
+    svg::{bar_chart, line_chart, stacked_bar_chart, Series, Stack},
+};
+
+fn to_f64(amount: Decimal) -> f64 {
+    amount.to_f64().unwrap_or_default()
+}
+
+/// Monthly bar chart of an account, including the accounts under it, as seen
+/// from its normal balance
+pub fn account_chart(
+    ledger: &Ledger,
+    account_code: &str,
+    cost_center: Option<&str>,
+) -> crate::Result<String> {
+    let account = ledger
+        .find_account_by_reference(account_code)
+        .ok_or_else(|| Error::UnrecognisedAccountCode(account_code.to_string()))?;
+    if let Some(cost_center) = cost_center {
+        if !ledger.cost_centers().contains(&cost_center) {
+            return Err(Error::UnrecognisedCostCenterCode(cost_center.to_string()));
+        }
+    }
+
+    let mut amounts = vec![Decimal::ZERO; 12];
+    for entry in ledger.journal.iter() {
+        for posting in entry.postings.iter() {
+            if cost_center.is_some() && posting.cost_center.as_deref() != cost_center {
+                continue;
+            }
+            if is_under(ledger, &posting.account_code, &account.code) {
+                amounts[entry.date.month0() as usize] += posting.amount;
+            }
+        }
+    }
+
+    let title = match cost_center {
+        Some(cost_center) => format!(
+            "{} - {} ({})",
+            account.code, account.description, cost_center
+        ),
+        None => format!("{} - {}", account.code, account.description),
+    };
+    let normal_balance = account.normal_balance();
+    let values: Vec<f64> = amounts
+        .into_iter()
+        .map(|amount| to_f64(normal_balance.presented(amount)))
+        .collect();
+
+    Ok(bar_chart(&title, &month_labels(&Period::Year), &values))
+}
+
+/// Per month the income and the expenses, each stacked by cost center
+pub fn income_expense_chart(ledger: &Ledger) -> crate::Result<String> {
+    let statements = IncomeStatements::new(ledger, Period::Year, None)?;
+    let per_cost_center = statements
+        .statements
+        .iter()
+        .filter_map(|statement| Some((statement.cost_center.clone()?, statement)));
+
+    let mut income = Stack {
+        name: "income".to_string(),
+        series: vec![],
+    };
+    let mut expenses = Stack {
+        name: "expenses".to_string(),
+        series: vec![],
+    };
+    for (cost_center, statement) in per_cost_center {
+        income.series.push(Series {
+            name: cost_center.clone(),
+            values: statement
+                .income
+                .amounts
+                .iter()
+                .copied()
+                .map(to_f64)
+                .collect(),
+        });
+        expenses.series.push(Series {
+            name: cost_center,
+            values: statement
+                .expenses
+                .amounts
+                .iter()
+                .copied()
+                .map(to_f64)
+                .collect(),
+        });
+    }
+
+    Ok(stacked_bar_chart(
+        "Income and expenses per cost center",
+        &statements.months,
+        &[income, expenses],
+    ))
+}
+
+/// Balance of a bank account over the year, starting from the balance before
+/// the earliest imported transaction when the bank reported it
+pub fn bank_balance_chart(ledger: &Ledger, iban: &str) -> crate::Result<String> {
+    let bank_account = ledger
+        .bank_accounts()
+        .into_iter()
+        .find(|bank_account| bank_account.iban.eq_ignore_ascii_case(iban))
+        .ok_or_else(|| Error::UnrecognisedBankAccount(iban.to_string()))?;
+    let account_code = ledger
+        .find_bank_account_code(&bank_account.iban)
+        .ok_or_else(|| Error::UnrecognisedBankAccount(iban.to_string()))?;
+
+    let opening = ledger
+        .bank_transactions()
+        .iter()
+        .filter(|transaction| transaction.iban == bank_account.iban)
+        .min_by_key(|transaction| transaction.date)
+        .and_then(|transaction| transaction.balance_before)
+        .unwrap_or_default();
+
+    let mut mutations: Vec<(NaiveDate, Decimal)> = ledger
+        .journal
+        .iter()
+        .flat_map(|entry| {
+            entry
+                .postings
+                .iter()
+                .filter(|posting| posting.account_code == account_code)
+                .map(|posting| (entry.date, posting.amount))
+        })
+        .collect();
+    mutations.sort_by_key(|(date, _)| *date);
+
+    let mut balance = opening;
+    let mut points = vec![(0.0, to_f64(opening))];
+    for (date, amount) in mutations {
+        balance += amount;
+        let days = NaiveDate::from_ymd_opt(date.year(), 12, 31)
+            .map(|last_day| last_day.ordinal())
+            .unwrap_or(365);
+        let x = f64::from(date.ordinal() - 1) / f64::from(days - 1);
+        match points.last_mut() {
+            Some(last) if last.0 == x => last.1 = to_f64(balance),
+            _ => points.push((x, to_f64(balance))),
+        }
+    }
+
+    let title = format!(
+        "Balance of {} - {}",
+        bank_account.iban, bank_account.description
+    );
+    Ok(line_chart(&title, &month_labels(&Period::Year), &points))
+}
+
+/// Whether the account is the ancestor or the account itself
+fn is_under(ledger: &Ledger, account_code: &str, ancestor_code: &str) -> bool {
+    let mut current = ledger.accounts.get(account_code);
+    while let Some(account) = current {
+        if account.code == ancestor_code {
+            return true;
+        }
+        current = account
+            .parent_code
+            .as_ref()
+            .and_then(|parent_code| ledger.accounts.get(parent_code));
+    }
+
+    false
+}
@@ -0,0 +1,338 @@
+use std::{
+    collections::{hash_map::Entry, HashMap},
+    io::Write,
+    str::FromStr,
+};
+
+use chrono::NaiveDate;
+
+use crate::{account_tree, AccountType, Error, JournalEntry, Ledger};
+
+use super::round_amount;
+
+/// Plain-text accounting syntaxes the journal can be exported to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    Ledger,
+    Hledger,
+    Beancount,
+}
+
+impl FromStr for ExportFormat {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "ledger" => Ok(Self::Ledger),
+            "hledger" => Ok(Self::Hledger),
+            "beancount" => Ok(Self::Beancount),
+            _ => Err(Error::UnrecognisedExportFormat(s.to_string())),
+        }
+    }
+}
+
+impl ExportFormat {
+    /// The customary file extension of the format
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Self::Ledger => "ledger",
+            Self::Hledger => "journal",
+            Self::Beancount => "beancount",
+        }
+    }
+}
+
+/// Writes the accounts and the journal entries, ordered by date
+///
+/// Accounts are named by their type followed by the codes of their ancestors,
+/// e.g. `Expenses:housing:utilities`; accounts without a type are placed under
+/// `Equity`. Cost centers become posting tags (metadata for beancount), relation
+/// names the payees and the entry and bank transaction ids entry metadata.
+/// Beancount accounts are opened on the date of their first posting.
+pub fn export_journal<W: Write>(
+    ledger: &Ledger,
+    format: ExportFormat,
+    mut writer: W,
+) -> crate::Result<()> {
+    let currency = ledger.currency().iso_alpha_code;
+    let exponent = ledger.currency().exponent;
+
+    let mut names: HashMap<String, &str> = HashMap::new();
+    for (_, account) in account_tree(&ledger.accounts) {
+        let name = account_name(ledger, &account.code, format);
+        match names.entry(name) {
+            Entry::Occupied(occupied) => {
+                return Err(Error::ExportAccountNameCollision {
+                    name: occupied.key().clone(),
+                    first: occupied.get().to_string(),
+                    second: account.code.clone(),
+                })
+            }
+            Entry::Vacant(vacant) => {
+                vacant.insert(&account.code);
+            }
+        }
+    }
+
+    let mut first_postings: HashMap<&str, NaiveDate> = HashMap::new();
+    for entry in ledger.journal.iter() {
+        for posting in entry.postings.iter() {
+            first_postings
+                .entry(&posting.account_code)
+                .and_modify(|date| *date = (*date).min(entry.date))
+                .or_insert(entry.date);
+        }
+    }
+
+    for (_, account) in account_tree(&ledger.accounts) {
+        let name = account_name(ledger, &account.code, format);
+        match format {
+            ExportFormat::Ledger | ExportFormat::Hledger => {
+                writeln!(writer, "account {}", name)?;
+                writeln!(writer, "    ; {}", single_line(&account.description))?;
+            }
+            ExportFormat::Beancount => {
+                // Accounts without postings are open for the whole year
+                let opened = first_postings
+                    .get(account.code.as_str())
+                    .copied()
+                    .or_else(|| NaiveDate::from_ymd_opt(ledger.year() as i32, 1, 1))
+                    .unwrap_or_default()
+                    .format("%Y-%m-%d");
+                writeln!(writer, "{} open {} {}", opened, name, currency)?;
+                writeln!(writer, "  description: {}", quoted(&account.description))?;
+            }
+        }
+    }
+
+    let mut entries: Vec<&JournalEntry> = ledger.journal.iter().collect();
+    entries.sort_by_key(|entry| entry.date);
+    for entry in entries {
+        writeln!(writer)?;
+        let date = entry.date.format("%Y-%m-%d");
+        let description = single_line(&entry.description);
+        let payee = entry.payee.as_deref().map(single_line);
+        match format {
+            ExportFormat::Ledger => {
+                writeln!(
+                    writer,
+                    "{} * {}",
+                    date,
+                    payee.as_deref().unwrap_or(&description)
+                )?;
+                writeln!(writer, "    ; {}", description)?;
+                writeln!(writer, "    ; id: {}", entry.id)?;
+                if let Some(transaction_id) = &entry.bank_transaction_id {
+                    writeln!(writer, "    ; bank_transaction: {}", transaction_id)?;
+                }
+            }
+            ExportFormat::Hledger => {
+                match &payee {
+                    Some(payee) => writeln!(writer, "{} * {} | {}", date, payee, description)?,
+                    None => writeln!(writer, "{} * {}", date, description)?,
+                }
+                writeln!(writer, "    ; id:{}", entry.id)?;
+                if let Some(transaction_id) = &entry.bank_transaction_id {
+                    writeln!(writer, "    ; bank_transaction:{}", transaction_id)?;
+                }
+            }
+            ExportFormat::Beancount => {
+                match &payee {
+                    Some(payee) => writeln!(
+                        writer,
+                        "{} * {} {}",
+                        date,
+                        quoted(payee),
+                        quoted(&description)
+                    )?,
+                    None => writeln!(writer, "{} * {}", date, quoted(&description))?,
+                }
+                writeln!(writer, "  id: {}", quoted(&entry.id))?;
+                if let Some(transaction_id) = &entry.bank_transaction_id {
+                    writeln!(writer, "  bank_transaction: {}", quoted(transaction_id))?;
+                }
+            }
+        }
+
+        for posting in entry.postings.iter() {
+            let name = account_name(ledger, &posting.account_code, format);
+            let amount = round_amount(posting.amount, exponent);
+            match (format, &posting.cost_center) {
+                (ExportFormat::Beancount, cost_center) => {
+                    writeln!(writer, "  {}  {} {}", name, amount, currency)?;
+                    if let Some(cost_center) = cost_center {
+                        writeln!(writer, "    cost_center: {}", quoted(cost_center))?;
+                    }
+                }
+                (_, Some(cost_center)) => writeln!(
+                    writer,
+                    "    {}  {} {}  ; cost_center: {}",
+                    name, amount, currency, cost_center
+                )?,
+                (_, None) => writeln!(writer, "    {}  {} {}", name, amount, currency)?,
+            }
+        }
+    }
+    writer.flush()?;
+
+    Ok(())
+}
+
+/// The type of the account followed by the codes from the top of its tree down
+fn account_name(ledger: &Ledger, account_code: &str, format: ExportFormat) -> String {
+    let mut codes = vec![];
+    let mut account_type = None;
+    let mut current = ledger.accounts.get(account_code);
+    while let Some(account) = current {
+        codes.push(account.code.as_str());
+        account_type = account.account_type;
+        current = account
+            .parent_code
+            .as_ref()
+            .and_then(|parent_code| ledger.accounts.get(parent_code));
+    }
+    if codes.is_empty() {
+        codes.push(account_code);
+    }
+
+    let root = match account_type {
+        Some(AccountType::Asset) => "Assets",
+        Some(AccountType::Liability) => "Liabilities",
+        Some(AccountType::Income) => "Income",
+        Some(AccountType::Expense) => "Expenses",
+        Some(AccountType::Equity) | None => "Equity",
+    };
+    std::iter::once(root.to_string())
+        .chain(codes.iter().rev().map(|code| match format {
+            ExportFormat::Beancount => beancount_component(code),
+            ExportFormat::Ledger | ExportFormat::Hledger => code.replace(':', "-"),
+        }))
+        .collect::<Vec<_>>()
+        .join(":")
+}
+
+/// Beancount account components start with a capital or digit and only have
+/// letters, digits and dashes
+fn beancount_component(code: &str) -> String {
+    let component: String = code
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
+        .collect();
+    let mut chars = component.chars();
+    match chars.next() {
+        Some(first) if first.is_ascii_alphanumeric() => {
+            first.to_ascii_uppercase().to_string() + chars.as_str()
+        }
+        _ => format!("X{}", component),
+    }
+}
+
+fn single_line(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+fn quoted(text: &str) -> String {
+    format!(
+        "\"{}\"",
+        single_line(text).replace('\\', "\\\\").replace('"', "\\\"")
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use rust_decimal::Decimal;
+
+    use super::*;
+    use crate::{Account, Posting};
+
+    fn account(
+        code: &str,
+        account_type: Option<AccountType>,
+        parent_code: Option<&str>,
+    ) -> Account {
+        Account {
+            code: code.to_string(),
+            description: format!("The {} account", code),
+            account_type,
+            parent_code: parent_code.map(str::to_string),
+            normal_balance: None,
+        }
+    }
+
+    fn ledger(accounts: Vec<Account>) -> Ledger {
+        let mut ledger = Ledger::default();
+        ledger.accounts = accounts
+            .into_iter()
+            .map(|account| (account.code.clone(), account))
+            .collect();
+        ledger.journal = vec![JournalEntry {
+            id: "e1".to_string(),
+            date: NaiveDate::from_ymd_opt(2022, 3, 5).unwrap(),
+            description: "Groceries \"weekly\"".to_string(),
+            payee: Some("Bakker".to_string()),
+            postings: vec![
+                Posting {
+                    account_code: "bank".to_string(),
+                    cost_center: Some("Hers".to_string()),
+                    amount: Decimal::new(-1250, 2),
+                },
+                Posting {
+                    account_code: "food".to_string(),
+                    cost_center: Some("Hers".to_string()),
+                    amount: Decimal::new(1250, 2),
+                },
+            ],
+            batch_id: None,
+            bank_transaction_id: Some("t1".to_string()),
+        }];
+        ledger
+    }
+
+    fn export(ledger: &Ledger, format: ExportFormat) -> crate::Result<String> {
+        let mut output = vec![];
+        export_journal(ledger, format, &mut output)?;
+        Ok(String::from_utf8(output).unwrap())
+    }
+
+    fn accounts() -> Vec<Account> {
+        vec![
+            account("bank", Some(AccountType::Asset), None),
+            account("living", Some(AccountType::Expense), None),
+            account("food", None, Some("living")),
+        ]
+    }
+
+    #[test]
+    fn beancount_opens_accounts_at_their_first_posting() {
+        let output = export(&ledger(accounts()), ExportFormat::Beancount).unwrap();
+
+        assert!(output.contains("2022-03-05 open Assets:Bank USD\n"));
+        assert!(output.contains("2022-03-05 open Expenses:Living:Food USD\n"));
+        assert!(output.contains(
+            "2022-03-05 * \"Bakker\" \"Groceries \\\"weekly\\\"\"\n  id: \"e1\"\n  bank_transaction: \"t1\"\n"
+        ));
+        assert!(output.contains("  Expenses:Living:Food  12.50 USD\n    cost_center: \"Hers\"\n"));
+    }
+
+    #[test]
+    fn ledger_and_hledger_keep_the_codes() {
+        let output = export(&ledger(accounts()), ExportFormat::Ledger).unwrap();
+        assert!(output.contains("account Expenses:living:food\n    ; The food account\n"));
+        assert!(output.contains("2022-03-05 * Bakker\n    ; Groceries \"weekly\"\n    ; id: e1\n"));
+        assert!(output.contains("    Assets:bank  -12.50 USD  ; cost_center: Hers\n"));
+
+        let output = export(&ledger(accounts()), ExportFormat::Hledger).unwrap();
+        assert!(output.contains("2022-03-05 * Bakker | Groceries \"weekly\"\n    ; id:e1\n"));
+    }
+
+    #[test]
+    fn beancount_names_that_differ_in_case_collide() {
+        let mut accounts = accounts();
+        accounts.push(account("Food", None, Some("living")));
+
+        assert!(matches!(
+            export(&ledger(accounts), ExportFormat::Beancount),
+            Err(Error::ExportAccountNameCollision { .. })
+        ));
+    }
+}
@@ -0,0 +1,219 @@
+use std::{collections::HashMap, io::Write};
+
+use chrono::Datelike;
+use rust_decimal::Decimal;
+use serde::Serialize;
+
+use crate::{account_tree, AccountType, CostCentersRepository, Error, Ledger, Period};
+
+use super::{month_labels, round_amount};
+
+#[derive(Serialize, Debug)]
+pub struct StatementLine {
+    pub account_code: String,
+    pub description: String,
+    pub depth: usize,
+    pub has_children: bool,
+    /// One amount per month of the period, including the descendants of the account
+    pub amounts: Vec<Decimal>,
+    pub total: Decimal,
+}
+
+/// The income or the expense accounts of a statement; amounts are positive
+/// for income respectively expenses
+#[derive(Serialize, Debug)]
+pub struct StatementSection {
+    pub lines: Vec<StatementLine>,
+    pub amounts: Vec<Decimal>,
+    pub total: Decimal,
+}
+
+#[derive(Serialize, Debug)]
+pub struct IncomeStatement {
+    /// `None` for the statement of all cost centers combined
+    pub cost_center: Option<String>,
+    pub income: StatementSection,
+    pub expenses: StatementSection,
+    /// Income minus expenses per month
+    pub result: Vec<Decimal>,
+    pub result_total: Decimal,
+}
+
+/// Income and expenses per month, combined and per cost center
+#[derive(Serialize, Debug)]
+pub struct IncomeStatements {
+    pub period: String,
+    /// Abbreviated names of the months of the period
+    pub months: Vec<String>,
+    pub statements: Vec<IncomeStatement>,
+}
+
+impl IncomeStatements {
+    /// Without a cost center this gives the combined statement followed by one
+    /// per cost center; with a cost center only the statement of that cost center
+    pub fn new(ledger: &Ledger, period: Period, cost_center: Option<&str>) -> crate::Result<Self> {
+        let cost_centers = ledger.cost_centers();
+        let statements = match cost_center {
+            Some(cost_center) if !cost_centers.contains(&cost_center) => {
+                return Err(Error::UnrecognisedCostCenterCode(cost_center.to_string()))
+            }
+            Some(cost_center) => vec![IncomeStatement::new(ledger, period, Some(cost_center))],
+            None => std::iter::once(None)
+                .chain(cost_centers.into_iter().map(Some))
+                .map(|cost_center| IncomeStatement::new(ledger, period, cost_center))
+                .collect(),
+        };
+
+        Ok(Self {
+            period: period.to_string(),
+            months: month_labels(&period),
+            statements,
+        })
+    }
+
+    /// One row per account, total and result of each statement, with the months as columns
+    pub fn write_csv<W: Write>(&self, writer: W) -> crate::Result<()> {
+        let mut csv_writer = csv::Writer::from_writer(writer);
+
+        let mut header = vec!["Cost center", "Section", "Account", "Description"];
+        header.extend(self.months.iter().map(String::as_str));
+        header.push("Total");
+        csv_writer.write_record(&header)?;
+
+        for statement in self.statements.iter() {
+            let cost_center = statement.cost_center.as_deref().unwrap_or("All");
+            for (section_name, section) in [
+                ("Income", &statement.income),
+                ("Expenses", &statement.expenses),
+            ] {
+                for line in section.lines.iter() {
+                    csv_writer.write_record(amounts_record(
+                        [
+                            cost_center,
+                            section_name,
+                            &line.account_code,
+                            &line.description,
+                        ],
+                        &line.amounts,
+                        line.total,
+                    ))?;
+                }
+                csv_writer.write_record(amounts_record(
+                    [cost_center, section_name, "", "Total"],
+                    &section.amounts,
+                    section.total,
+                ))?;
+            }
+            csv_writer.write_record(amounts_record(
+                [cost_center, "Result", "", "Income minus expenses"],
+                &statement.result,
+                statement.result_total,
+            ))?;
+        }
+        csv_writer.flush()?;
+
+        Ok(())
+    }
+}
+
+fn amounts_record(labels: [&str; 4], amounts: &[Decimal], total: Decimal) -> Vec<String> {
+    labels
+        .iter()
+        .map(|label| label.to_string())
+        .chain(amounts.iter().map(Decimal::to_string))
+        .chain(std::iter::once(total.to_string()))
+        .collect()
+}
+
+impl IncomeStatement {
+    fn new(ledger: &Ledger, period: Period, cost_center: Option<&str>) -> Self {
+        let first_month = period.first_month();
+        let months = period.as_range().count();
+
+        let mut balances: HashMap<&str, Vec<Decimal>> = HashMap::new();
+        for entry in ledger
+            .journal
+            .iter()
+            .filter(|entry| period.contains(ledger.year(), entry.date))
+        {
+            let month_index = (entry.date.month() - first_month) as usize;
+            for posting in entry.postings.iter() {
+                if cost_center.is_some() && posting.cost_center.as_deref() != cost_center {
+                    continue;
+                }
+                let mut current = ledger.accounts.get(&posting.account_code);
+                while let Some(account) = current {
+                    balances
+                        .entry(account.code.as_str())
+                        .or_insert_with(|| vec![Decimal::ZERO; months])[month_index] +=
+                        posting.amount;
+                    current = account
+                        .parent_code
+                        .as_ref()
+                        .and_then(|parent_code| ledger.accounts.get(parent_code));
+                }
+            }
+        }
+
+        let exponent = ledger.currency().exponent;
+        let section = |account_type: AccountType| {
+            let presented = |amount: Decimal| {
+                round_amount(account_type.normal_balance().presented(amount), exponent)
+            };
+            let mut amounts = vec![Decimal::ZERO; months];
+            let mut lines = vec![];
+            for (depth, account) in account_tree(&ledger.accounts) {
+                if account.account_type != Some(account_type) {
+                    continue;
+                }
+                let balance = match balances.get(account.code.as_str()) {
+                    Some(balance) if balance.iter().any(|amount| !amount.is_zero()) => balance,
+                    _ => continue,
+                };
+                // The section total adds up the tops of the trees of this type
+                let parent_type = account
+                    .parent_code
+                    .as_ref()
+                    .and_then(|parent_code| ledger.accounts.get(parent_code))
+                    .and_then(|parent| parent.account_type);
+                if parent_type != Some(account_type) {
+                    for (total, amount) in amounts.iter_mut().zip(balance) {
+                        *total += amount;
+                    }
+                }
+                lines.push(StatementLine {
+                    account_code: account.code.clone(),
+                    description: account.description.clone(),
+                    depth,
+                    has_children: ledger
+                        .accounts
+                        .values()
+                        .any(|child| child.parent_code.as_ref() == Some(&account.code)),
+                    amounts: balance.iter().copied().map(presented).collect(),
+                    total: presented(balance.iter().sum()),
+                })
+            }
+
+            StatementSection {
+                lines,
+                total: presented(amounts.iter().sum()),
+                amounts: amounts.into_iter().map(presented).collect(),
+            }
+        };
+
+        let income = section(AccountType::Income);
+        let expenses = section(AccountType::Expense);
+        Self {
+            cost_center: cost_center.map(str::to_string),
+            result: income
+                .amounts
+                .iter()
+                .zip(expenses.amounts.iter())
+                .map(|(income, expenses)| income - expenses)
+                .collect(),
+            result_total: income.total - expenses.total,
+            income,
+            expenses,
+        }
+    }
+}
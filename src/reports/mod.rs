@@ -0,0 +1,40 @@
+mod trial_balance;
+pub use trial_balance::*;
+
+mod income_statement;
+pub use income_statement::*;
+
+mod budget;
+pub use budget::*;
+
+mod charts;
+pub use charts::*;
+
+mod export;
+pub use export::*;
+
+mod svg;
+
+use chrono::NaiveDate;
+use rust_decimal::Decimal;
+
+use crate::Period;
+
+/// Rounds the amount to, and shows it with, the decimals of the currency
+fn round_amount(amount: Decimal, exponent: u32) -> Decimal {
+    let mut amount = amount.round_dp(exponent);
+    if amount.is_zero() {
+        amount = Decimal::ZERO;
+    }
+    amount.rescale(exponent);
+    amount
+}
+
+/// Abbreviated names of the months of the period
+fn month_labels(period: &Period) -> Vec<String> {
+    period
+        .as_range()
+        .filter_map(|month| NaiveDate::from_ymd_opt(2000, month, 1))
+        .map(|date| date.format("%b").to_string())
+        .collect()
+}
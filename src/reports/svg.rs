@@ -0,0 +1,268 @@
+// Minimal SVG drawing of the report charts; the charts are plain markup
+// without scripts, so they can be served under the Content-Security-Policy
+
+use std::fmt::Write;
+
+const WIDTH: f64 = 720.0;
+const HEIGHT: f64 = 360.0;
+const MARGIN_LEFT: f64 = 70.0;
+const MARGIN_RIGHT: f64 = 20.0;
+const MARGIN_TOP: f64 = 40.0;
+const MARGIN_BOTTOM: f64 = 60.0;
+
+const COLORS: [&str; 8] = [
+    "#4e79a7", "#f28e2b", "#59a14f", "#e15759", "#76b7b2", "#edc948", "#b07aa1", "#9c755f",
+];
+
+/// Values of one name, one per label of the chart
+pub struct Series {
+    pub name: String,
+    pub values: Vec<f64>,
+}
+
+/// A group of series stacked onto one bar per label
+pub struct Stack {
+    pub name: String,
+    pub series: Vec<Series>,
+}
+
+/// Vertical scale of the plot area, always including zero
+struct Scale {
+    min: f64,
+    max: f64,
+    step: f64,
+}
+
+impl Scale {
+    fn new(values: impl Iterator<Item = f64>) -> Self {
+        let (min, max) = values.fold((0.0_f64, 0.0_f64), |(min, max), value| {
+            (min.min(value), max.max(value))
+        });
+        let step = nice_step(max - min);
+        Self {
+            min: (min / step).floor() * step,
+            max: ((max / step).ceil() * step).max(step),
+            step,
+        }
+    }
+
+    fn y(&self, value: f64) -> f64 {
+        let height = HEIGHT - MARGIN_TOP - MARGIN_BOTTOM;
+        MARGIN_TOP + height * (self.max - value) / (self.max - self.min)
+    }
+}
+
+/// 1, 2 or 5 times a power of ten, giving about five grid lines
+fn nice_step(range: f64) -> f64 {
+    if range <= 0.0 {
+        return 1.0;
+    }
+    let rough = range / 5.0;
+    let magnitude = 10_f64.powf(rough.log10().floor());
+    match rough / magnitude {
+        fraction if fraction <= 1.0 => magnitude,
+        fraction if fraction <= 2.0 => 2.0 * magnitude,
+        fraction if fraction <= 5.0 => 5.0 * magnitude,
+        _ => 10.0 * magnitude,
+    }
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn plot_width() -> f64 {
+    WIDTH - MARGIN_LEFT - MARGIN_RIGHT
+}
+
+fn start(title: &str) -> String {
+    let mut svg = String::new();
+    let _ = write!(
+        svg,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 {w} {h}" width="{w}" height="{h}" font-family="sans-serif" font-size="11">"#,
+        w = WIDTH,
+        h = HEIGHT
+    );
+    let _ = write!(
+        svg,
+        r#"<title>{title}</title><text x="{x}" y="20" font-size="14" text-anchor="middle">{title}</text>"#,
+        title = escape(title),
+        x = WIDTH / 2.0
+    );
+    svg
+}
+
+/// Horizontal grid lines with their values, the zero line and the labels below
+/// the slots
+fn axes(svg: &mut String, scale: &Scale, labels: &[String]) {
+    let decimals = (-scale.step.log10().floor()).max(0.0) as usize;
+    let mut value = scale.min;
+    while value <= scale.max + scale.step / 2.0 {
+        let y = scale.y(value);
+        let _ = write!(
+            svg,
+            r##"<line x1="{x1}" y1="{y:.1}" x2="{x2}" y2="{y:.1}" stroke="#ddd"/><text x="{tx}" y="{ty:.1}" text-anchor="end">{value:.decimals$}</text>"##,
+            x1 = MARGIN_LEFT,
+            x2 = WIDTH - MARGIN_RIGHT,
+            tx = MARGIN_LEFT - 5.0,
+            ty = y + 4.0,
+        );
+        value += scale.step;
+    }
+    let _ = write!(
+        svg,
+        r##"<line x1="{x1}" y1="{y:.1}" x2="{x2}" y2="{y:.1}" stroke="#333"/>"##,
+        x1 = MARGIN_LEFT,
+        x2 = WIDTH - MARGIN_RIGHT,
+        y = scale.y(0.0),
+    );
+
+    let slot = plot_width() / labels.len().max(1) as f64;
+    for (index, label) in labels.iter().enumerate() {
+        let _ = write!(
+            svg,
+            r#"<text x="{x:.1}" y="{y}" text-anchor="middle">{label}</text>"#,
+            x = MARGIN_LEFT + slot * (index as f64 + 0.5),
+            y = HEIGHT - MARGIN_BOTTOM + 15.0,
+            label = escape(label),
+        );
+    }
+}
+
+fn legend(svg: &mut String, names: &[&str]) {
+    let y = HEIGHT - 20.0;
+    let mut x = MARGIN_LEFT;
+    for (index, name) in names.iter().enumerate() {
+        let _ = write!(
+            svg,
+            r#"<rect x="{x}" y="{ry}" width="10" height="10" fill="{color}"/><text x="{tx}" y="{y}">{name}</text>"#,
+            ry = y - 9.0,
+            color = COLORS[index % COLORS.len()],
+            tx = x + 14.0,
+            name = escape(name),
+        );
+        x += 24.0 + 7.0 * name.chars().count() as f64;
+    }
+}
+
+fn bar(svg: &mut String, x: f64, width: f64, from: f64, to: f64, color: &str, tooltip: &str) {
+    let _ = write!(
+        svg,
+        r#"<rect x="{x:.1}" y="{y:.1}" width="{width:.1}" height="{height:.1}" fill="{color}"><title>{tooltip}</title></rect>"#,
+        y = from.min(to),
+        height = (to - from).abs(),
+        tooltip = escape(tooltip),
+    );
+}
+
+pub fn bar_chart(title: &str, labels: &[String], values: &[f64]) -> String {
+    let scale = Scale::new(values.iter().copied());
+    let mut svg = start(title);
+    axes(&mut svg, &scale, labels);
+
+    let slot = plot_width() / labels.len().max(1) as f64;
+    for (index, (label, value)) in labels.iter().zip(values).enumerate() {
+        bar(
+            &mut svg,
+            MARGIN_LEFT + slot * (index as f64 + 0.15),
+            slot * 0.7,
+            scale.y(0.0),
+            scale.y(*value),
+            COLORS[0],
+            &format!("{}: {:.2}", label, value),
+        );
+    }
+
+    svg.push_str("</svg>");
+    svg
+}
+
+/// Per label one bar per stack, side by side; series with the same name get
+/// the same color in every stack
+pub fn stacked_bar_chart(title: &str, labels: &[String], stacks: &[Stack]) -> String {
+    let mut names: Vec<&str> = vec![];
+    for series in stacks.iter().flat_map(|stack| stack.series.iter()) {
+        if !names.contains(&series.name.as_str()) {
+            names.push(&series.name);
+        }
+    }
+    let totals = stacks.iter().flat_map(|stack| {
+        (0..labels.len()).flat_map(move |index| {
+            let values = stack.series.iter().map(move |series| series.values[index]);
+            [
+                values.clone().filter(|value| *value > 0.0).sum::<f64>(),
+                values.filter(|value| *value < 0.0).sum::<f64>(),
+            ]
+        })
+    });
+    let scale = Scale::new(totals);
+
+    let mut svg = start(title);
+    axes(&mut svg, &scale, labels);
+
+    let slot = plot_width() / labels.len().max(1) as f64;
+    let bar_width = slot * 0.8 / stacks.len().max(1) as f64;
+    for (index, label) in labels.iter().enumerate() {
+        for (stack_index, stack) in stacks.iter().enumerate() {
+            let x = MARGIN_LEFT + slot * (index as f64 + 0.1) + bar_width * stack_index as f64;
+            let (mut positive, mut negative) = (0.0, 0.0);
+            for series in stack.series.iter() {
+                let value = series.values[index];
+                let base = if value < 0.0 {
+                    &mut negative
+                } else {
+                    &mut positive
+                };
+                let color_index = names.iter().position(|name| *name == series.name);
+                bar(
+                    &mut svg,
+                    x,
+                    bar_width * 0.95,
+                    scale.y(*base),
+                    scale.y(*base + value),
+                    COLORS[color_index.unwrap_or_default() % COLORS.len()],
+                    &format!("{} {}, {}: {:.2}", label, stack.name, series.name, value),
+                );
+                *base += value;
+            }
+            let _ = write!(
+                svg,
+                r#"<text x="{x:.1}" y="{y}" text-anchor="middle" font-size="8">{name}</text>"#,
+                x = x + bar_width / 2.0,
+                y = HEIGHT - MARGIN_BOTTOM + 27.0,
+                name = escape(&stack.name.chars().take(3).collect::<String>()),
+            );
+        }
+    }
+
+    legend(&mut svg, &names);
+    svg.push_str("</svg>");
+    svg
+}
+
+/// A line through the points, with `x` from 0 (left) to 1 (right) and the labels
+/// evenly spread below
+pub fn line_chart(title: &str, labels: &[String], points: &[(f64, f64)]) -> String {
+    let scale = Scale::new(points.iter().map(|(_, y)| *y));
+    let mut svg = start(title);
+    axes(&mut svg, &scale, labels);
+
+    let path: Vec<String> = points
+        .iter()
+        .map(|(x, y)| format!("{:.1},{:.1}", MARGIN_LEFT + plot_width() * x, scale.y(*y)))
+        .collect();
+    if !path.is_empty() {
+        let _ = write!(
+            svg,
+            r#"<polyline points="{points}" fill="none" stroke="{color}" stroke-width="2"/>"#,
+            points = path.join(" "),
+            color = COLORS[0],
+        );
+    }
+
+    svg.push_str("</svg>");
+    svg
+}
@@ -0,0 +1,192 @@
+use std::collections::{BTreeMap, HashMap};
+
+use rust_decimal::Decimal;
+use serde::Serialize;
+
+use crate::{account_tree, AccountType, CostCentersRepository, Error, Ledger, Period};
+
+use super::round_amount;
+
+/// Opening balance, mutations and closing balance of an account over a period;
+/// balances are debit positive
+#[derive(Serialize, Debug, Clone, Copy, Default, PartialEq)]
+pub struct BalanceTotals {
+    pub opening: Decimal,
+    pub debit: Decimal,
+    pub credit: Decimal,
+    pub closing: Decimal,
+}
+
+impl BalanceTotals {
+    fn add_opening(&mut self, amount: Decimal) {
+        self.opening += amount;
+        self.closing += amount;
+    }
+
+    fn add_mutation(&mut self, amount: Decimal) {
+        if amount.is_sign_negative() {
+            self.credit -= amount;
+        } else {
+            self.debit += amount;
+        }
+        self.closing += amount;
+    }
+
+    fn add(&mut self, other: &BalanceTotals) {
+        self.opening += other.opening;
+        self.debit += other.debit;
+        self.credit += other.credit;
+        self.closing += other.closing;
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.opening.is_zero()
+            && self.debit.is_zero()
+            && self.credit.is_zero()
+            && self.closing.is_zero()
+    }
+
+    /// Rounded to, and shown with, the decimals of the currency
+    fn rounded(self, exponent: u32) -> Self {
+        let round = |amount: Decimal| round_amount(amount, exponent);
+        Self {
+            opening: round(self.opening),
+            debit: round(self.debit),
+            credit: round(self.credit),
+            closing: round(self.closing),
+        }
+    }
+}
+
+#[derive(Serialize, Debug)]
+pub struct CostCenterTotals {
+    /// `None` for postings without cost center
+    pub cost_center: Option<String>,
+    #[serde(flatten)]
+    pub totals: BalanceTotals,
+}
+
+#[derive(Serialize, Debug)]
+pub struct TrialBalanceLine {
+    pub account_code: String,
+    pub description: String,
+    pub account_type: Option<AccountType>,
+    pub depth: usize,
+    pub has_children: bool,
+    /// Postings on the account itself
+    #[serde(flatten)]
+    pub totals: BalanceTotals,
+    /// Postings on the account and its descendants
+    pub subtotal: BalanceTotals,
+    pub cost_centers: Vec<CostCenterTotals>,
+}
+
+/// Totals per account and per cost center of the journal over a period
+#[derive(Serialize, Debug)]
+pub struct TrialBalance {
+    pub period: String,
+    pub cost_center: Option<String>,
+    pub lines: Vec<TrialBalanceLine>,
+    pub total: BalanceTotals,
+    /// Whether the grand total nets to zero, as it should for a valid journal
+    pub balanced: bool,
+}
+
+impl TrialBalance {
+    /// Postings before the period make up the opening balances; postings after
+    /// it are left out. With a cost center only its postings count.
+    pub fn new(ledger: &Ledger, period: Period, cost_center: Option<&str>) -> crate::Result<Self> {
+        if let Some(cost_center) = cost_center {
+            if !ledger.cost_centers().contains(&cost_center) {
+                return Err(Error::UnrecognisedCostCenterCode(cost_center.to_string()));
+            }
+        }
+
+        let mut per_cost_center: BTreeMap<(&str, Option<&str>), BalanceTotals> = BTreeMap::new();
+        for entry in ledger.journal.iter() {
+            let before_period = period.starts_after(ledger.year(), entry.date);
+            if !before_period && !period.contains(ledger.year(), entry.date) {
+                continue;
+            }
+            for posting in entry.postings.iter() {
+                let posting_cost_center = posting.cost_center.as_deref();
+                if cost_center.is_some() && posting_cost_center != cost_center {
+                    continue;
+                }
+                let totals = per_cost_center
+                    .entry((posting.account_code.as_str(), posting_cost_center))
+                    .or_default();
+                if before_period {
+                    totals.add_opening(posting.amount);
+                } else {
+                    totals.add_mutation(posting.amount);
+                }
+            }
+        }
+
+        let mut per_account: HashMap<&str, BalanceTotals> = HashMap::new();
+        let mut subtotals: HashMap<&str, BalanceTotals> = HashMap::new();
+        let mut total = BalanceTotals::default();
+        for ((account_code, _), totals) in per_cost_center.iter() {
+            per_account.entry(account_code).or_default().add(totals);
+            total.add(totals);
+
+            let mut current = ledger.accounts.get(*account_code);
+            while let Some(account) = current {
+                subtotals
+                    .entry(account.code.as_str())
+                    .or_default()
+                    .add(totals);
+                current = account
+                    .parent_code
+                    .as_ref()
+                    .and_then(|parent_code| ledger.accounts.get(parent_code));
+            }
+        }
+
+        let exponent = ledger.currency().exponent;
+        let lines = account_tree(&ledger.accounts)
+            .into_iter()
+            .filter_map(|(depth, account)| {
+                let code = account.code.as_str();
+                let subtotal = subtotals.get(code).copied().unwrap_or_default();
+                if subtotal.is_zero() {
+                    return None;
+                }
+                Some(TrialBalanceLine {
+                    account_code: account.code.clone(),
+                    description: account.description.clone(),
+                    account_type: account.account_type,
+                    depth,
+                    has_children: ledger
+                        .accounts
+                        .values()
+                        .any(|child| child.parent_code.as_deref() == Some(code)),
+                    totals: per_account
+                        .get(code)
+                        .copied()
+                        .unwrap_or_default()
+                        .rounded(exponent),
+                    subtotal: subtotal.rounded(exponent),
+                    cost_centers: per_cost_center
+                        .iter()
+                        .filter(|((account_code, _), _)| *account_code == code)
+                        .map(|((_, cost_center), totals)| CostCenterTotals {
+                            cost_center: cost_center.map(str::to_string),
+                            totals: totals.rounded(exponent),
+                        })
+                        .collect(),
+                })
+            })
+            .collect();
+
+        let total = total.rounded(exponent);
+        Ok(Self {
+            period: period.to_string(),
+            cost_center: cost_center.map(str::to_string),
+            lines,
+            balanced: total.closing.is_zero() && total.debit == total.credit,
+            total,
+        })
+    }
+}
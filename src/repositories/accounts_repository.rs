@@ -1,15 +1,11 @@
-use crate::{Account, AssignByContractDefinition, AssignByDescription, AssignByNameSearch};
+use crate::{Account, AssignmentPolicy, AssignmentRule, RuleInput, RuleMatch};
 
 pub trait AccountsRepository {
     fn find_account_by_reference(&self, reference: &str) -> Option<&Account>;
-    fn search_account_by_name(&self, name: &str) -> Option<(&AssignByNameSearch, String)>;
-    fn search_account_by_contract(
-        &self,
-        contract_code: &str,
-    ) -> Option<&AssignByContractDefinition>;
-    fn search_account_by_description(
-        &self,
-        key: &str,
-        description: &str,
-    ) -> Option<&AssignByDescription>;
+    /// The asset account of one of the own bank accounts
+    fn find_bank_account_code(&self, iban: &str) -> Option<&str>;
+    /// All matching rules, highest priority first; between equal priorities in the
+    /// order of ledger.yaml
+    fn find_assignment_rules(&self, input: &RuleInput) -> Vec<(&AssignmentRule, RuleMatch)>;
+    fn assignment_policy(&self) -> AssignmentPolicy;
 }
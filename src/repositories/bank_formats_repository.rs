@@ -0,0 +1,9 @@
+use std::collections::HashMap;
+
+use crate::{BankFormat, BankImporter, DetectedFormat};
+
+pub trait BankFormatsRepository {
+    fn bank_formats(&self) -> &HashMap<String, BankFormat>;
+    fn find_bank_importer(&self, format_code: &str) -> Option<&dyn BankImporter>;
+    fn detect_bank_format(&self, contents: &[u8]) -> Vec<DetectedFormat>;
+}
@@ -1,8 +1,181 @@
-use std::collections::HashMap;
+use std::{collections::HashMap, fs::File, io::ErrorKind};
+
+use serde::Serialize;
 
 use crate::BankTransaction;
 
-#[allow(dead_code)]
+/// Outcome of adding a bank transaction to the imported ones
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImportStatus {
+    New,
+    AlreadyImported,
+    Changed,
+}
+
+#[derive(Serialize, Debug, Default)]
+pub struct ImportCounts {
+    pub new: usize,
+    pub already_imported: usize,
+    pub changed: usize,
+}
+
+impl ImportCounts {
+    pub fn count(&mut self, status: ImportStatus) {
+        match status {
+            ImportStatus::New => self.new += 1,
+            ImportStatus::AlreadyImported => self.already_imported += 1,
+            ImportStatus::Changed => self.changed += 1,
+        }
+    }
+}
+
+/// The bank transactions imported so far, keyed by their id
+#[derive(Debug, Default)]
 pub struct BankTransactions {
     records: HashMap<String, BankTransaction>,
 }
+
+impl BankTransactions {
+    /// Reads the stored transactions; a missing file means nothing was imported yet
+    pub fn load(file_name: &str) -> crate::Result<Self> {
+        let file = match File::open(file_name) {
+            Ok(file) => file,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(Self::default()),
+            Err(e) => return Err(e.into()),
+        };
+
+        let transactions: Vec<BankTransaction> = serde_yaml::from_reader(file)?;
+        Ok(Self {
+            records: transactions
+                .into_iter()
+                .map(|transaction| (transaction.id.clone(), transaction))
+                .collect(),
+        })
+    }
+
+    /// Writes the transactions ordered by date, so the file can be compared between imports
+    pub fn save(&self, file_name: &str) -> crate::Result<()> {
+        let mut transactions: Vec<&BankTransaction> = self.records.values().collect();
+        transactions.sort_by(|one, other| one.date.cmp(&other.date).then(one.id.cmp(&other.id)));
+
+        let file = File::create(file_name)?;
+        serde_yaml::to_writer(file, &transactions)?;
+
+        Ok(())
+    }
+
+    /// What importing the transaction would do, without importing it; only the data from the bank
+    /// counts, not how either was assigned
+    pub fn status(&self, transaction: &BankTransaction) -> ImportStatus {
+        match self.records.get(&transaction.id) {
+            Some(existing) if existing.same_bank_data(transaction) => ImportStatus::AlreadyImported,
+            Some(_) => ImportStatus::Changed,
+            None => ImportStatus::New,
+        }
+    }
+
+    /// Adds or replaces the transaction with the same id; an already imported one keeps its
+    /// assignment
+    pub fn import(&mut self, transaction: BankTransaction) -> ImportStatus {
+        let status = self.status(&transaction);
+        if status != ImportStatus::AlreadyImported {
+            self.records.insert(transaction.id.clone(), transaction);
+        }
+        status
+    }
+
+    pub fn remove(&mut self, id: &str) -> Option<BankTransaction> {
+        self.records.remove(id)
+    }
+
+    pub fn get(&self, id: &str) -> Option<&BankTransaction> {
+        self.records.get(id)
+    }
+
+    pub fn len(&self) -> usize {
+        self.records.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.records.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &BankTransaction> {
+        self.records.values()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+    use rust_decimal::Decimal;
+
+    use super::*;
+    use crate::{ing::DescriptionProperties, AssignmentReason};
+
+    fn transaction() -> BankTransaction {
+        BankTransaction {
+            id: "t1".to_string(),
+            iban: "NL91ABNA0417164300".to_string(),
+            date: NaiveDate::from_ymd_opt(2022, 3, 1).unwrap(),
+            cost_center: "Hers".to_string(),
+            relation_name: Some("Bakker".to_string()),
+            relation_iban: None,
+            attributes: HashMap::from([
+                (
+                    DescriptionProperties::DESCRIPTION.to_string(),
+                    "brood".to_string(),
+                ),
+                (
+                    DescriptionProperties::REFERENCE.to_string(),
+                    "123".to_string(),
+                ),
+            ]),
+            amount: Decimal::new(-350, 2),
+            balance_before: Some(Decimal::new(1000, 2)),
+            balance_after: Some(Decimal::new(650, 2)),
+            account_code: None,
+            assignment_reason: None,
+            assigned_by: None,
+            assignment_warning: None,
+            splits: vec![],
+        }
+    }
+
+    #[test]
+    fn reimport_keeps_the_stored_assignment() {
+        let mut transactions = BankTransactions::default();
+        let mut assigned = transaction();
+        assigned.account_code = Some("4000".to_string());
+        assigned.assignment_reason = Some(AssignmentReason::Manual);
+        assigned.attributes.insert(
+            DescriptionProperties::DESCRIPTION.to_string(),
+            "boodschappen".to_string(),
+        );
+        transactions.import(assigned.clone());
+
+        assert_eq!(
+            transactions.import(transaction()),
+            ImportStatus::AlreadyImported
+        );
+        assert_eq!(transactions.get("t1"), Some(&assigned));
+    }
+
+    #[test]
+    fn other_bank_data_replaces_the_stored_transaction() {
+        let mut transactions = BankTransactions::default();
+        transactions.import(transaction());
+
+        let mut changed = transaction();
+        changed.balance_after = Some(Decimal::new(600, 2));
+        assert_eq!(transactions.import(changed.clone()), ImportStatus::Changed);
+        assert_eq!(transactions.get("t1"), Some(&changed));
+
+        let mut changed = transaction();
+        changed.attributes.insert(
+            DescriptionProperties::REFERENCE.to_string(),
+            "456".to_string(),
+        );
+        assert_eq!(transactions.status(&changed), ImportStatus::Changed);
+    }
+}
@@ -0,0 +1,7 @@
+use crate::{BankTransaction, BankTransactions, ImportStatus};
+
+pub trait BankTransactionsRepository {
+    fn bank_transactions(&self) -> &BankTransactions;
+    fn import_bank_transaction(&mut self, transaction: BankTransaction) -> ImportStatus;
+    fn save_bank_transactions(&self) -> crate::Result<()>;
+}
@@ -1,3 +1,5 @@
 pub trait CostCentersRepository {
     fn find_cost_center_by_iban(&self, iban: &str) -> Option<String>;
+    /// The cost center codes, sorted
+    fn cost_centers(&self) -> Vec<&str>;
 }
@@ -0,0 +1,16 @@
+use crate::{BankTransaction, ImportBatch};
+
+pub trait ImportBatchesRepository {
+    /// All batches of this ledger, the most recent first
+    fn import_batches(&self) -> Vec<&ImportBatch>;
+    fn find_import_batch(&self, batch_id: &str) -> Option<&ImportBatch>;
+    fn stage_import_batch(&mut self, batch: ImportBatch) -> crate::Result<()>;
+    /// Replaces the transaction with the same id in the staged batches
+    fn update_staged_transaction(&mut self, transaction: &BankTransaction) -> crate::Result<()>;
+    /// Registers the new transactions of a staged batch in the journal, returns
+    /// the number of journal entries created
+    fn commit_import_batch(&mut self, batch_id: &str) -> crate::Result<usize>;
+    /// Removes the journal entries a committed batch created, or discards a staged
+    /// one; returns the number of journal entries removed
+    fn rollback_import_batch(&mut self, batch_id: &str) -> crate::Result<usize>;
+}
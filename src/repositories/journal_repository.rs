@@ -16,6 +16,12 @@ pub enum JournalRepositoryError {
 
     #[error("Urecognised account code '{0}'")]
     UnrecognisedAccountCode(String),
+
+    #[error("Journal entry '{id}' does not balance: the postings sum up to {balance}")]
+    UnbalancedEntry {
+        id: String,
+        balance: rust_decimal::Decimal,
+    },
 }
 pub trait JournalRepository {
     fn load_journal(&mut self) -> Result<(), JournalRepositoryError>;
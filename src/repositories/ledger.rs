@@ -1,20 +1,33 @@
 use std::{
     collections::{HashMap, HashSet},
     fs::File,
+    io::ErrorKind,
 };
 
-use regex::Regex;
-use rust_decimal::RoundingStrategy;
+use chrono::Utc;
+use indexmap::IndexMap;
+use rust_decimal::{Decimal, RoundingStrategy};
 use rusty_money::iso::{self, Currency};
-use serde::{Deserialize, Serialize};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use tracing::warn;
 
 use crate::{
-    Account, AccountHibernate, AccountsRepository, AssignByContractDefinition, AssignByDescription,
-    AssignByDescriptionDefinition, AssignByNameSearch, BankAccount, BankFormat,
-    CostCentersRepository, Error, Journal, JournalEntry, JournalRepository, Relation,
-    RelationsRepository, Result,
+    account_subtotals, account_tree, allocate_splits, ing::DescriptionProperties,
+    resolve_account_tree, shadowed_rules, sort_by_priority, validate_splits, Account,
+    AccountHibernate, AccountType, AccountsRepository, AssignByContractDefinition,
+    AssignByDescriptionDefinition, AssignByNameDefinition, AssignmentPolicy, AssignmentRule,
+    AssignmentRuleDefinition, AssignmentRuleHibernate, BankAccount, BankFormat,
+    BankFormatsRepository, BankImporter, BankImporters, BankTransaction, BankTransactions,
+    BankTransactionsRepository, Budget, CostCentersRepository, DetectedFormat, Error, ImportBatch,
+    ImportBatchStatus, ImportBatchesRepository, ImportStatus, Journal, JournalEntry,
+    JournalRepository, JournalRepositoryError, LegacyJournalEntry, Posting, Relation,
+    RelationsRepository, Result, RuleInput, RuleMatch, Split, StoredJournalEntry,
 };
 
+const BANK_TRANSACTIONS_FILE: &str = "bank_transactions.yaml";
+const IMPORT_BATCHES_FILE: &str = "import_batches.yaml";
+const SUSPENSE_ACCOUNT: &str = "suspense";
+
 #[derive(Serialize, Deserialize, Debug)]
 pub struct Ledger {
     #[serde(default)]
@@ -27,25 +40,39 @@ pub struct Ledger {
     currency: &'static Currency,
 
     bank_formats: HashMap<String, BankFormat>,
+    #[serde(skip)]
+    bank_importers: BankImporters,
     cost_centers: HashSet<String>,
     bank_accounts: HashMap<String, BankAccount>,
     relations: HashMap<String, Relation>,
     pub(crate) accounts: HashMap<String, Account>,
+    #[serde(default)]
+    suspense_account: Option<String>,
 
+    #[serde(default)]
+    assignment_policy: AssignmentPolicy,
+    #[serde(rename = "assignment_rules", default)]
+    assignment_rules_definition: Vec<AssignmentRuleDefinition>,
     #[serde(rename = "assign_by_name")]
-    assign_by_name_definition: HashMap<String, Vec<String>>,
+    assign_by_name_definition: IndexMap<String, Vec<AssignByNameDefinition>>,
     #[serde(rename = "assign_by_description")]
-    assign_by_description_definition: HashMap<String, Vec<AssignByDescriptionDefinition>>,
+    assign_by_description_definition: IndexMap<String, Vec<AssignByDescriptionDefinition>>,
 
-    assign_by_contract: HashMap<String, AssignByContractDefinition>,
+    assign_by_contract: IndexMap<String, AssignByContractDefinition>,
 
+    #[serde(default)]
+    budgets: Vec<Budget>,
+
+    /// All assignment rules, highest priority first
     #[serde(skip)]
-    assign_by_name: Vec<AssignByNameSearch>,
-    #[serde(skip)]
-    pub assign_by_description: HashMap<String, Vec<AssignByDescription>>,
+    assignment_rules: Vec<AssignmentRule>,
 
     #[serde(skip)]
     pub journal: Vec<JournalEntry>,
+    #[serde(skip)]
+    bank_transactions: BankTransactions,
+    #[serde(skip)]
+    import_batches: Vec<ImportBatch>,
 }
 
 impl Ledger {
@@ -61,6 +88,8 @@ impl Ledger {
             result.currency = currency;
         }
 
+        result.bank_importers = BankImporters::from_formats(&result.bank_formats)?;
+
         for (iban, bank_account) in result.bank_accounts.iter_mut() {
             bank_account.iban = iban.to_owned();
             if !result.cost_centers.contains(&bank_account.cost_center_code) {
@@ -68,73 +97,152 @@ impl Ledger {
                     bank_account.cost_center_code.clone(),
                 ));
             }
+
+            match &bank_account.account_code {
+                Some(account_code) if !result.accounts.contains_key(account_code) => {
+                    return Err(Error::UnrecognisedAccountCode(account_code.clone()))
+                }
+                Some(_) => {}
+                None => {
+                    result
+                        .accounts
+                        .entry(iban.clone())
+                        .or_insert_with(|| Account {
+                            code: iban.clone(),
+                            description: bank_account.description.clone(),
+                            account_type: Some(AccountType::Asset),
+                            parent_code: None,
+                            normal_balance: None,
+                        });
+                    bank_account.account_code = Some(iban.clone());
+                }
+            }
         }
 
         for (account_code, account) in result.accounts.iter_mut() {
             account.code = account_code.to_owned();
         }
+        if let Some(account_code) = &result.suspense_account {
+            if !result.accounts.contains_key(account_code) {
+                return Err(Error::UnrecognisedAccountCode(account_code.clone()));
+            }
+        }
+        resolve_account_tree(&mut result.accounts)?;
 
         for (relation_reference, relation) in result.relations.iter_mut() {
             relation.reference = relation_reference.clone();
         }
 
-        result.assign_by_name = vec![];
+        result.load_assignment_rules()?;
 
-        for (account_code, search_terms) in result.assign_by_name_definition.iter() {
-            let account = result.find_account_by_reference(account_code.as_str());
-            let account_code = account_code.to_string();
-            if account.is_none() {
-                return Err(Error::UnrecognisedAccountCode(account_code));
+        let mut budgeted = HashSet::new();
+        for budget in result.budgets.iter() {
+            if !result.accounts.contains_key(&budget.account_code) {
+                return Err(Error::UnrecognisedAccountCode(budget.account_code.clone()));
             }
-            for search_term in search_terms {
-                let search_expression = Regex::new(
-                    format!(
-                        "(?i){}",
-                        search_term.replace(r#"{naam}"#, r#"\w+(\s+\w+)?"#)
-                    )
-                    .as_str(),
-                )?;
-                result.assign_by_name.push(AssignByNameSearch {
-                    account_code: account_code.clone(),
-                    name: search_term.clone(),
-                    search_expression,
-                })
-            }
-        }
-
-        for (key, search_list) in result.assign_by_description_definition.iter() {
-            // let account = result.find_account_by_reference(account_code.as_str());
-            let mut list = vec![];
-            for assign_definition in search_list.iter() {
-                match result.find_account_by_reference(assign_definition.account_code.as_str()) {
-                    Some(_) => {
-                        let assign_by_description =
-                            AssignByDescription::try_from(assign_definition)?;
-                        list.push(assign_by_description);
-                    }
-                    None => {
-                        return Err(Error::UnrecognisedAccountCode(
-                            assign_definition.account_code.clone(),
-                        ))
-                    }
+            if let Some(cost_center) = &budget.cost_center {
+                if !result.cost_centers.contains(cost_center) {
+                    return Err(Error::UnrecognisedCostCenterCode(cost_center.clone()));
                 }
             }
-
-            result.assign_by_description.insert(key.to_string(), list);
+            budget.validate()?;
+            if !budgeted.insert((&budget.account_code, &budget.cost_center)) {
+                return Err(Error::InvalidBudget {
+                    account_code: budget.account_code.clone(),
+                    reason: "the account has more than one budget for the same cost center"
+                        .to_string(),
+                });
+            }
         }
 
-        // result.load_journal()?;
+        result.load_journal().map_err(|e| match e {
+            JournalRepositoryError::UnrecognisedAccountCode(account_code) => {
+                Error::UnrecognisedAccountCode(account_code)
+            }
+            JournalRepositoryError::UnbalancedEntry { id, balance } => {
+                Error::UnbalancedJournalEntry { id, balance }
+            }
+            e => e.into(),
+        })?;
+        result.bank_transactions =
+            BankTransactions::load(&result.file_name(BANK_TRANSACTIONS_FILE))?;
+        result.import_batches = read_yaml_or_default(&result.file_name(IMPORT_BATCHES_FILE))?;
 
         Ok(result)
     }
 
+    /// The chart of accounts in tree order, with the subtotals of the journal
     pub fn accounts_for_hibernate(&self) -> Vec<AccountHibernate> {
-        self.accounts
+        let subtotals = account_subtotals(&self.accounts, &self.account_balances());
+        let parent_codes: HashSet<&str> = self
+            .accounts
             .values()
-            .map(|account_model| AccountHibernate::from(account_model))
+            .filter_map(|account| account.parent_code.as_deref())
+            .collect();
+
+        account_tree(&self.accounts)
+            .into_iter()
+            .map(|(depth, account_model)| {
+                let subtotal = subtotals
+                    .get(&account_model.code)
+                    .copied()
+                    .unwrap_or_default();
+                AccountHibernate {
+                    depth,
+                    indent: "\u{a0}\u{a0}".repeat(depth),
+                    has_children: parent_codes.contains(account_model.code.as_str()),
+                    subtotal: format!(
+                        "{:.*}",
+                        self.currency.exponent as usize,
+                        account_model.normal_balance().presented(subtotal)
+                    ),
+                    ..AccountHibernate::from(account_model)
+                }
+            })
             .collect()
     }
 
+    /// Sum of the postings per account in the journal, debit positive
+    pub fn account_balances(&self) -> HashMap<String, Decimal> {
+        let mut balances: HashMap<String, Decimal> = HashMap::new();
+        for posting in self.journal.iter().flat_map(|entry| entry.postings.iter()) {
+            *balances.entry(posting.account_code.clone()).or_default() += posting.amount;
+        }
+
+        balances
+    }
+
+    /// The own bank accounts, sorted by IBAN
+    pub fn bank_accounts(&self) -> Vec<&BankAccount> {
+        let mut bank_accounts: Vec<&BankAccount> = self.bank_accounts.values().collect();
+        bank_accounts.sort_by(|one, other| one.iban.cmp(&other.iban));
+        bank_accounts
+    }
+
+    pub fn year(&self) -> u32 {
+        self.year
+    }
+
+    pub fn budgets(&self) -> &[Budget] {
+        &self.budgets
+    }
+
+    pub fn currency(&self) -> &'static Currency {
+        self.currency
+    }
+
+    /// Checks the splits of a rule or manual assignment, and their accounts
+    pub fn check_splits(&self, splits: &[Split]) -> Result<()> {
+        if let Some(split) = splits
+            .iter()
+            .find(|split| !self.accounts.contains_key(&split.account_code))
+        {
+            return Err(Error::UnrecognisedAccountCode(split.account_code.clone()));
+        }
+
+        validate_splits(splits)
+    }
+
     pub fn file_name(&self, base_name: &str) -> String {
         format!(
             "./data/organisations/{}/{}/{}",
@@ -144,13 +252,22 @@ impl Ledger {
 }
 
 impl Journal for Ledger {
+    fn register(&mut self, entry: JournalEntry) -> crate::Result<String> {
+        let entry = self.prepare_journal_entry(entry)?;
+        let id = entry.id.clone();
+        self.journal.push(entry);
+
+        Ok(id)
+    }
+
     fn register_single(
         &mut self,
         date: chrono::NaiveDate,
         account_code: &str,
+        counter_account_code: &str,
         amount: &rusty_money::Money<Currency>,
         description: &str,
-    ) -> crate::Result<()> {
+    ) -> crate::Result<String> {
         if amount.currency() != self.currency {
             return Err(Error::CurrencyMustBeExchanged {
                 ledger: self.currency.to_string(),
@@ -158,25 +275,292 @@ impl Journal for Ledger {
             });
         }
 
-        if !self.accounts.contains_key(account_code) {
-            return Err(Error::UnrecognisedAccountCode(account_code.to_string()));
+        let amount = *amount.amount();
+        self.register(JournalEntry {
+            id: String::new(),
+            date,
+            description: description.to_string(),
+            payee: None,
+            postings: vec![
+                Posting {
+                    account_code: account_code.to_string(),
+                    cost_center: None,
+                    amount,
+                },
+                Posting {
+                    account_code: counter_account_code.to_string(),
+                    cost_center: None,
+                    amount: -amount,
+                },
+            ],
+            batch_id: None,
+            bank_transaction_id: None,
+        })
+    }
+}
+
+impl Ledger {
+    /// Posts the amount of a legacy entry on its account and the opposite on the
+    /// bank account, or on the suspense account when the bank account is unknown
+    fn convert_legacy_entry(&self, index: usize, legacy: LegacyJournalEntry) -> JournalEntry {
+        let bank_account = match &legacy.iban {
+            Some(iban) => self
+                .bank_accounts
+                .values()
+                .find(|bank_account| bank_account.iban.eq_ignore_ascii_case(iban)),
+            None if self.bank_accounts.len() == 1 => self.bank_accounts.values().next(),
+            None => None,
+        };
+
+        let cost_center = bank_account.map(|bank_account| bank_account.cost_center_code.clone());
+        let counter_account_code = bank_account
+            .and_then(|bank_account| bank_account.account_code.clone())
+            .unwrap_or_else(|| self.suspense_account_code().to_string());
+
+        JournalEntry {
+            id: format!("legacy-{}", index + 1),
+            date: legacy.date,
+            description: legacy.description,
+            payee: None,
+            postings: vec![
+                Posting {
+                    account_code: legacy.account_code,
+                    cost_center: cost_center.clone(),
+                    amount: legacy.amount,
+                },
+                Posting {
+                    account_code: counter_account_code,
+                    cost_center,
+                    amount: -legacy.amount,
+                },
+            ],
+            batch_id: None,
+            bank_transaction_id: None,
         }
+    }
 
-        let decimal = amount.amount();
-        let amount = decimal.round_dp_with_strategy(
+    fn suspense_account_code(&self) -> &str {
+        self.suspense_account.as_deref().unwrap_or(SUSPENSE_ACCOUNT)
+    }
+
+    /// The entry of an assigned bank transaction: the amount is posted on the
+    /// asset account of the bank account, and countered on the assigned account
+    ///
+    /// With splits, the counter postings are the shares of the splits, and whatever
+    /// they leave goes to the assigned account.
+    pub fn bank_transaction_entry(&self, transaction: &BankTransaction) -> Result<JournalEntry> {
+        let bank_account_code = self
+            .find_bank_account_code(&transaction.iban)
+            .ok_or_else(|| Error::UnrecognisedBankAccount(transaction.iban.clone()))?;
+        let account_code = transaction
+            .account_code
+            .as_ref()
+            .ok_or_else(|| Error::BankTransactionNotAssigned(transaction.id.clone()))?;
+        let cost_center = Some(transaction.cost_center.clone());
+
+        let mut postings = vec![Posting {
+            account_code: bank_account_code.to_string(),
+            cost_center: cost_center.clone(),
+            amount: transaction.amount,
+        }];
+        let shares = allocate_splits(
+            transaction.amount,
+            &transaction.splits,
+            account_code,
             self.currency.exponent,
-            RoundingStrategy::MidpointAwayFromZero,
-        );
+        )?;
+        for (account_code, amount) in shares {
+            postings.push(Posting {
+                account_code,
+                cost_center: cost_center.clone(),
+                amount: -amount,
+            });
+        }
 
-        self.journal.push(JournalEntry {
-            date,
-            account_code: account_code.to_string(),
-            amount,
-            description: description.to_string(),
-        });
+        self.prepare_journal_entry(JournalEntry {
+            id: String::new(),
+            date: transaction.date,
+            description: transaction.journal_description(),
+            payee: transaction.relation_name.clone(),
+            postings,
+            batch_id: None,
+            bank_transaction_id: Some(transaction.id.clone()),
+        })
+    }
+
+    /// Compiles the rules of the `assignment_rules` and `assign_by_*` sections
+    /// and the tags of the accounts into a single list, highest priority first
+    fn load_assignment_rules(&mut self) -> Result<()> {
+        let mut rules = vec![];
+
+        for (index, definition) in self.assignment_rules_definition.iter().enumerate() {
+            if !self.accounts.contains_key(&definition.account_code) {
+                return Err(Error::UnrecognisedAccountCode(
+                    definition.account_code.clone(),
+                ));
+            }
+            if let Some(cost_center) = &definition.when.cost_center {
+                if !self.cost_centers.contains(cost_center) {
+                    return Err(Error::UnrecognisedCostCenterCode(cost_center.clone()));
+                }
+            }
+            self.check_splits(&definition.splits)?;
+            rules.push(AssignmentRule::from_definition(index, definition)?);
+        }
+
+        for (key, search_list) in self.assign_by_description_definition.iter() {
+            for definition in search_list.iter() {
+                if !self.accounts.contains_key(&definition.account_code) {
+                    return Err(Error::UnrecognisedAccountCode(
+                        definition.account_code.clone(),
+                    ));
+                }
+                self.check_splits(&definition.splits)?;
+                rules.push(AssignmentRule::by_description(key, definition)?);
+            }
+        }
+
+        for (contract_code, definition) in self.assign_by_contract.iter() {
+            if !self.accounts.contains_key(&definition.account_code) {
+                return Err(Error::UnrecognisedAccountCode(
+                    definition.account_code.clone(),
+                ));
+            }
+            self.check_splits(&definition.splits)?;
+            rules.push(AssignmentRule::by_contract(
+                contract_code,
+                definition,
+                DescriptionProperties::CONTRACT,
+            )?);
+        }
+
+        let mut account_codes: Vec<&String> = self.accounts.keys().collect();
+        account_codes.sort();
+        for account_code in account_codes {
+            rules.push(AssignmentRule::by_tag(
+                account_code,
+                DescriptionProperties::TAG,
+            )?);
+        }
+
+        for (account_code, definitions) in self.assign_by_name_definition.iter() {
+            if !self.accounts.contains_key(account_code) {
+                return Err(Error::UnrecognisedAccountCode(account_code.clone()));
+            }
+            for definition in definitions {
+                rules.push(AssignmentRule::by_name(account_code, definition)?);
+            }
+        }
+
+        sort_by_priority(&mut rules);
+        self.assignment_rules = rules;
+
+        Ok(())
+    }
+
+    /// The compiled assignment rules in the order they are tried
+    pub fn assignment_rules_for_hibernate(&self) -> Vec<AssignmentRuleHibernate> {
+        self.assignment_rules
+            .iter()
+            .map(AssignmentRuleHibernate::from)
+            .collect()
+    }
+
+    /// Assignment rules that can never match because an earlier rule always
+    /// matches as well
+    pub fn lint_assignment_rules(&self) -> Vec<String> {
+        shadowed_rules(&self.assignment_rules)
+            .into_iter()
+            .map(|(rule, shadowing)| {
+                format!(
+                    "{} (account {}) is shadowed by {} (account {})",
+                    rule.source(),
+                    rule.account_code,
+                    shadowing.source(),
+                    shadowing.account_code
+                )
+            })
+            .collect()
+    }
+
+    /// Gives the entry an id when it has none, rounds the amounts to the currency
+    /// and checks the accounts and the balance
+    fn prepare_journal_entry(&self, mut entry: JournalEntry) -> Result<JournalEntry> {
+        if entry.id.is_empty() {
+            entry.id = uuid::Uuid::new_v4().simple().to_string();
+        }
+
+        for posting in entry.postings.iter_mut() {
+            if !self.accounts.contains_key(&posting.account_code) {
+                return Err(Error::UnrecognisedAccountCode(posting.account_code.clone()));
+            }
+            posting.amount = posting.amount.round_dp_with_strategy(
+                self.currency.exponent,
+                RoundingStrategy::MidpointAwayFromZero,
+            );
+        }
+
+        if !entry.is_balanced() {
+            return Err(Error::UnbalancedJournalEntry {
+                balance: entry.balance(),
+                id: entry.id,
+            });
+        }
+
+        Ok(entry)
+    }
+
+    fn save_import_batches(&self) -> Result<()> {
+        let batches_file = File::create(self.file_name(IMPORT_BATCHES_FILE))?;
+        serde_yaml::to_writer(batches_file, &self.import_batches)?;
 
         Ok(())
     }
+
+    /// A batch committed after the committed batch at `index` with some of the same transactions;
+    /// rolling back the earlier one would remove or restore what the later one relies on
+    fn dependent_import_batch(&self, index: usize) -> Option<&ImportBatch> {
+        let batch = &self.import_batches[index];
+        if batch.status != ImportBatchStatus::Committed {
+            return None;
+        }
+        let transaction_ids: HashSet<&str> = batch
+            .added_transaction_ids
+            .iter()
+            .map(String::as_str)
+            .chain(
+                batch
+                    .replaced_transactions
+                    .iter()
+                    .map(|transaction| transaction.id.as_str()),
+            )
+            .collect();
+
+        self.import_batches.iter().find(|later| {
+            later.status == ImportBatchStatus::Committed
+                && later.committed_at > batch.committed_at
+                && later
+                    .transactions
+                    .iter()
+                    .any(|transaction| transaction_ids.contains(transaction.id.as_str()))
+        })
+    }
+
+    fn import_batch_index(&self, batch_id: &str) -> Result<usize> {
+        self.import_batches
+            .iter()
+            .position(|batch| batch.id == batch_id)
+            .ok_or_else(|| Error::UnrecognisedImportBatch(batch_id.to_string()))
+    }
+}
+
+/// Contents of a data file of the ledger; a missing file means there is nothing stored yet
+fn read_yaml_or_default<T: DeserializeOwned + Default>(file_name: &str) -> Result<T> {
+    match File::open(file_name) {
+        Ok(file) => Ok(serde_yaml::from_reader(file)?),
+        Err(e) if e.kind() == ErrorKind::NotFound => Ok(T::default()),
+        Err(e) => Err(e.into()),
+    }
 }
 
 fn default_currency() -> &'static Currency {
@@ -192,35 +576,87 @@ impl Default for Ledger {
             currency_iso: Default::default(),
             currency: default_currency(),
             bank_formats: Default::default(),
+            bank_importers: Default::default(),
             cost_centers: Default::default(),
             bank_accounts: Default::default(),
             relations: Default::default(),
             accounts: Default::default(),
+            suspense_account: Default::default(),
             assign_by_name_definition: Default::default(),
             assign_by_description_definition: Default::default(),
             assign_by_contract: Default::default(),
-            assign_by_name: Default::default(),
-            assign_by_description: Default::default(),
+            budgets: Default::default(),
+            assignment_policy: Default::default(),
+            assignment_rules_definition: Default::default(),
+            assignment_rules: Default::default(),
             journal: Default::default(),
+            bank_transactions: Default::default(),
+            import_batches: Default::default(),
         }
     }
 }
 
 impl JournalRepository for Ledger {
     fn load_journal(&mut self) -> std::result::Result<(), crate::JournalRepositoryError> {
-        let journal_file = File::open(self.file_name("journal.yaml"))?;
+        let journal_file = match File::open(self.file_name("journal.yaml")) {
+            Ok(journal_file) => journal_file,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(()),
+            Err(e) => return Err(e.into()),
+        };
         {
-            let mut journal: Vec<JournalEntry> = serde_yaml::from_reader(journal_file)?;
+            let stored: Vec<StoredJournalEntry> = serde_yaml::from_reader(journal_file)?;
+            let mut journal: Vec<JournalEntry> = stored
+                .into_iter()
+                .enumerate()
+                .map(|(index, stored_entry)| match stored_entry {
+                    StoredJournalEntry::Entry(entry) => entry,
+                    StoredJournalEntry::Legacy(legacy) => self.convert_legacy_entry(index, legacy),
+                })
+                .collect();
+            let suspense_account_code = self.suspense_account_code().to_string();
+            let suspended = journal
+                .iter()
+                .filter(|journal_entry| journal_entry.id.starts_with("legacy-"))
+                .filter(|journal_entry| {
+                    journal_entry
+                        .postings
+                        .iter()
+                        .any(|posting| posting.account_code == suspense_account_code)
+                })
+                .count();
+            if suspended > 0 {
+                warn!(
+                    "{} legacy journal entries without a known bank account are countered on '{}'",
+                    suspended, suspense_account_code
+                );
+                self.accounts
+                    .entry(suspense_account_code.clone())
+                    .or_insert_with(|| Account {
+                        code: suspense_account_code.clone(),
+                        description: "Suspense".to_string(),
+                        account_type: Some(AccountType::Equity),
+                        parent_code: None,
+                        normal_balance: None,
+                    });
+            }
             for journal_entry in journal.iter_mut() {
-                if !self.accounts.contains_key(&journal_entry.account_code) {
-                    return Err(crate::JournalRepositoryError::UnrecognisedAccountCode(
-                        journal_entry.account_code.to_owned(),
-                    ));
+                for posting in journal_entry.postings.iter_mut() {
+                    if !self.accounts.contains_key(&posting.account_code) {
+                        return Err(crate::JournalRepositoryError::UnrecognisedAccountCode(
+                            posting.account_code.to_owned(),
+                        ));
+                    }
+                    posting.amount = posting.amount.round_dp_with_strategy(
+                        self.currency.exponent,
+                        RoundingStrategy::MidpointAwayFromZero,
+                    );
+                }
+                if !journal_entry.is_balanced() {
+                    return Err(crate::JournalRepositoryError::UnbalancedEntry {
+                        id: journal_entry.id.clone(),
+                        balance: journal_entry.balance(),
+                    });
                 }
-                journal_entry.amount = journal_entry.amount.round_dp_with_strategy(
-                    self.currency.exponent,
-                    RoundingStrategy::MidpointAwayFromZero,
-                );
             }
             self.journal = journal;
         }
@@ -246,6 +682,12 @@ impl CostCentersRepository for Ledger {
             }
         })
     }
+
+    fn cost_centers(&self) -> Vec<&str> {
+        let mut cost_centers: Vec<&str> = self.cost_centers.iter().map(String::as_str).collect();
+        cost_centers.sort_unstable();
+        cost_centers
+    }
 }
 
 impl AccountsRepository for Ledger {
@@ -253,37 +695,211 @@ impl AccountsRepository for Ledger {
         self.accounts.get(reference)
     }
 
-    fn search_account_by_name(&self, name: &str) -> Option<(&AssignByNameSearch, String)> {
-        self.assign_by_name
+    fn find_bank_account_code(&self, iban: &str) -> Option<&str> {
+        self.bank_accounts.values().find_map(|bank_account| {
+            if bank_account.iban.eq_ignore_ascii_case(iban) {
+                bank_account.account_code.as_deref()
+            } else {
+                None
+            }
+        })
+    }
+
+    fn find_assignment_rules(&self, input: &RuleInput) -> Vec<(&AssignmentRule, RuleMatch)> {
+        self.assignment_rules
             .iter()
-            .find_map(|account_by_name_search| {
-                if let Some(capture) = account_by_name_search.search_expression.captures(name) {
-                    Some((account_by_name_search, capture[0].to_string()))
-                } else {
-                    None
+            .filter_map(|rule| Some((rule, rule.matches(input)?)))
+            .collect()
+    }
+
+    fn assignment_policy(&self) -> AssignmentPolicy {
+        self.assignment_policy
+    }
+}
+
+impl BankFormatsRepository for Ledger {
+    fn bank_formats(&self) -> &HashMap<String, BankFormat> {
+        &self.bank_formats
+    }
+
+    fn find_bank_importer(&self, format_code: &str) -> Option<&dyn BankImporter> {
+        self.bank_importers.find(format_code)
+    }
+
+    fn detect_bank_format(&self, contents: &[u8]) -> Vec<DetectedFormat> {
+        self.bank_importers.detect(contents)
+    }
+}
+
+impl BankTransactionsRepository for Ledger {
+    fn bank_transactions(&self) -> &BankTransactions {
+        &self.bank_transactions
+    }
+
+    fn import_bank_transaction(&mut self, transaction: BankTransaction) -> ImportStatus {
+        self.bank_transactions.import(transaction)
+    }
+
+    fn save_bank_transactions(&self) -> Result<()> {
+        self.bank_transactions
+            .save(&self.file_name(BANK_TRANSACTIONS_FILE))
+    }
+}
+
+impl ImportBatchesRepository for Ledger {
+    fn import_batches(&self) -> Vec<&ImportBatch> {
+        let mut batches: Vec<&ImportBatch> = self.import_batches.iter().collect();
+        batches.sort_by_key(|batch| std::cmp::Reverse(batch.timestamp));
+        batches
+    }
+
+    fn find_import_batch(&self, batch_id: &str) -> Option<&ImportBatch> {
+        self.import_batches
+            .iter()
+            .find(|batch| batch.id == batch_id)
+    }
+
+    fn stage_import_batch(&mut self, batch: ImportBatch) -> Result<()> {
+        self.import_batches.push(batch);
+        self.save_import_batches()
+    }
+
+    fn update_staged_transaction(&mut self, transaction: &BankTransaction) -> Result<()> {
+        let mut updated = false;
+        for batch in self.import_batches.iter_mut() {
+            if batch.status != ImportBatchStatus::Staged {
+                continue;
+            }
+            for staged in batch.transactions.iter_mut() {
+                if staged.id == transaction.id {
+                    *staged = transaction.clone();
+                    updated = true;
                 }
-            })
+            }
+        }
+
+        if updated {
+            self.save_import_batches()?;
+        }
+        Ok(())
     }
 
-    fn search_account_by_contract(
-        &self,
-        contract_code: &str,
-    ) -> Option<&AssignByContractDefinition> {
-        self.assign_by_contract.get(contract_code)
+    fn commit_import_batch(&mut self, batch_id: &str) -> Result<usize> {
+        let index = self.import_batch_index(batch_id)?;
+        let batch = &self.import_batches[index];
+        if batch.status != ImportBatchStatus::Staged {
+            return Err(Error::ImportBatchNotAllowed {
+                batch_id: batch_id.to_string(),
+                action: "committed",
+                status: batch.status,
+            });
+        }
+
+        // A transaction that occurs twice in the file is imported once
+        let mut seen = HashSet::new();
+        let transactions: Vec<BankTransaction> = batch
+            .transactions
+            .iter()
+            .filter(|transaction| seen.insert(transaction.id.as_str()))
+            .cloned()
+            .collect();
+
+        // Transactions that were imported before are already in the journal,
+        // changed ones are posted again
+        let mut journal_entries = vec![];
+        let mut added_transaction_ids = vec![];
+        let mut replaced_transactions = vec![];
+        let mut replaced_ids = HashSet::new();
+        for transaction in transactions.iter() {
+            match self.bank_transactions.status(transaction) {
+                ImportStatus::New => added_transaction_ids.push(transaction.id.clone()),
+                ImportStatus::Changed => {
+                    if let Some(stored) = self.bank_transactions.get(&transaction.id) {
+                        replaced_transactions.push(stored.clone());
+                    }
+                    replaced_ids.insert(transaction.id.clone());
+                }
+                ImportStatus::AlreadyImported => continue,
+            }
+
+            if transaction.account_code.is_some() {
+                let mut journal_entry = self.bank_transaction_entry(transaction)?;
+                journal_entry.batch_id = Some(batch_id.to_string());
+                journal_entries.push(journal_entry);
+            }
+        }
+
+        for transaction in transactions {
+            self.bank_transactions.import(transaction);
+        }
+        let (replaced_journal_entries, kept) = std::mem::take(&mut self.journal)
+            .into_iter()
+            .partition(|journal_entry: &JournalEntry| {
+                journal_entry
+                    .bank_transaction_id
+                    .as_ref()
+                    .is_some_and(|id| replaced_ids.contains(id))
+            });
+        self.journal = kept;
+        let created = journal_entries.len();
+        self.journal.append(&mut journal_entries);
+        let batch = &mut self.import_batches[index];
+        batch.status = ImportBatchStatus::Committed;
+        batch.committed_at = Some(Utc::now());
+        batch.added_transaction_ids = added_transaction_ids;
+        batch.replaced_transactions = replaced_transactions;
+        batch.replaced_journal_entries = replaced_journal_entries;
+
+        self.save_journal()?;
+        self.save_bank_transactions()?;
+        self.save_import_batches()?;
+
+        Ok(created)
     }
 
-    fn search_account_by_description(
-        &self,
-        key: &str,
-        description: &str,
-    ) -> Option<&AssignByDescription> {
-        match self.assign_by_description.get(key) {
-            Some(search_list) => search_list.iter().find(|assign_by_description| {
-                assign_by_description
-                    .search_expression
-                    .is_match(description)
+    fn rollback_import_batch(&mut self, batch_id: &str) -> Result<usize> {
+        let index = self.import_batch_index(batch_id)?;
+        if let Some(dependent) = self.dependent_import_batch(index) {
+            return Err(Error::ImportBatchHasDependents {
+                batch_id: batch_id.to_string(),
+                dependent_batch_id: dependent.id.clone(),
+            });
+        }
+
+        let batch = &mut self.import_batches[index];
+        match batch.status {
+            ImportBatchStatus::Staged => {
+                batch.status = ImportBatchStatus::RolledBack;
+                self.save_import_batches()?;
+                Ok(0)
+            }
+            ImportBatchStatus::Committed => {
+                batch.status = ImportBatchStatus::RolledBack;
+                for transaction_id in batch.added_transaction_ids.iter() {
+                    self.bank_transactions.remove(transaction_id);
+                }
+                for transaction in batch.replaced_transactions.iter() {
+                    self.bank_transactions.import(transaction.clone());
+                }
+
+                let before = self.journal.len();
+                self.journal
+                    .retain(|journal_entry| journal_entry.batch_id.as_deref() != Some(batch_id));
+                let removed = before - self.journal.len();
+                self.journal
+                    .append(&mut std::mem::take(&mut batch.replaced_journal_entries));
+
+                self.save_journal()?;
+                self.save_bank_transactions()?;
+                self.save_import_batches()?;
+
+                Ok(removed)
+            }
+            ImportBatchStatus::RolledBack => Err(Error::ImportBatchNotAllowed {
+                batch_id: batch_id.to_string(),
+                action: "rolled back",
+                status: batch.status,
             }),
-            None => None,
         }
     }
 }
@@ -293,3 +909,167 @@ impl RelationsRepository for Ledger {
         self.relations.get(reference)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use chrono::{NaiveDate, TimeZone};
+
+    use super::*;
+
+    const IBAN: &str = "NL91ABNA0417164300";
+
+    /// A ledger whose data files are removed when it is dropped
+    struct TestLedger(Ledger);
+
+    impl TestLedger {
+        fn new(name: &str) -> Self {
+            let mut ledger = Ledger {
+                id: format!("test-{}-{}", name, uuid::Uuid::new_v4().simple()),
+                year: 2022,
+                ..Default::default()
+            };
+            for (code, account_type) in
+                [("bank", AccountType::Asset), ("food", AccountType::Expense)]
+            {
+                ledger.accounts.insert(
+                    code.to_string(),
+                    Account {
+                        code: code.to_string(),
+                        description: code.to_string(),
+                        account_type: Some(account_type),
+                        parent_code: None,
+                        normal_balance: None,
+                    },
+                );
+            }
+            ledger.bank_accounts.insert(
+                IBAN.to_string(),
+                BankAccount {
+                    iban: IBAN.to_string(),
+                    cost_center_code: "Hers".to_string(),
+                    description: "Her bank".to_string(),
+                    account_code: Some("bank".to_string()),
+                },
+            );
+            std::fs::create_dir_all(ledger.file_name("")).unwrap();
+            Self(ledger)
+        }
+
+        fn stage(&mut self, minute: u32, transactions: Vec<BankTransaction>) -> String {
+            let id = format!("batch-{}", minute);
+            self.0
+                .stage_import_batch(ImportBatch {
+                    id: id.clone(),
+                    uploader: "test".to_string(),
+                    file_name: "test.csv".to_string(),
+                    format_code: "ing".to_string(),
+                    timestamp: Utc.with_ymd_and_hms(2022, 3, 1, 12, minute, 0).unwrap(),
+                    checksum: String::new(),
+                    status: ImportBatchStatus::Staged,
+                    committed_at: None,
+                    transactions,
+                    added_transaction_ids: vec![],
+                    replaced_transactions: vec![],
+                    replaced_journal_entries: vec![],
+                })
+                .unwrap();
+            id
+        }
+
+        fn entries_of(&self, transaction_id: &str) -> Vec<&JournalEntry> {
+            self.0
+                .journal
+                .iter()
+                .filter(|entry| entry.bank_transaction_id.as_deref() == Some(transaction_id))
+                .collect()
+        }
+    }
+
+    impl Drop for TestLedger {
+        fn drop(&mut self) {
+            let organisation = format!("./data/organisations/{}", self.0.id);
+            let _ = std::fs::remove_dir_all(organisation);
+            let _ = std::fs::remove_dir("./data/organisations");
+            let _ = std::fs::remove_dir("./data");
+        }
+    }
+
+    fn transaction(id: &str, amount: i64, balance_after: i64) -> BankTransaction {
+        BankTransaction {
+            id: id.to_string(),
+            iban: IBAN.to_string(),
+            date: NaiveDate::from_ymd_opt(2022, 3, 1).unwrap(),
+            cost_center: "Hers".to_string(),
+            relation_name: Some("Bakker".to_string()),
+            relation_iban: None,
+            attributes: HashMap::new(),
+            amount: Decimal::new(amount, 2),
+            balance_before: None,
+            balance_after: Some(Decimal::new(balance_after, 2)),
+            account_code: Some("food".to_string()),
+            assignment_reason: None,
+            assigned_by: None,
+            assignment_warning: None,
+            splits: vec![],
+        }
+    }
+
+    #[test]
+    fn commit_imports_a_repeated_transaction_once() {
+        let mut ledger = TestLedger::new("repeated");
+        let batch_id = ledger.stage(
+            0,
+            vec![transaction("t1", -350, 650), transaction("t1", -350, 650)],
+        );
+
+        assert_eq!(ledger.0.commit_import_batch(&batch_id).unwrap(), 1);
+        assert_eq!(ledger.0.bank_transactions.len(), 1);
+        assert_eq!(ledger.entries_of("t1").len(), 1);
+    }
+
+    #[test]
+    fn changed_transaction_is_posted_again_and_restored_by_rollback() {
+        let mut ledger = TestLedger::new("changed");
+        let first = ledger.stage(0, vec![transaction("t1", -350, 650)]);
+        ledger.0.commit_import_batch(&first).unwrap();
+
+        let second = ledger.stage(1, vec![transaction("t1", -400, 600)]);
+        ledger.0.commit_import_batch(&second).unwrap();
+        let entries = ledger.entries_of("t1");
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].batch_id.as_deref(), Some(second.as_str()));
+        assert_eq!(entries[0].postings[0].amount, Decimal::new(-400, 2));
+
+        assert_eq!(ledger.0.rollback_import_batch(&second).unwrap(), 1);
+        let entries = ledger.entries_of("t1");
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].batch_id.as_deref(), Some(first.as_str()));
+        assert_eq!(
+            ledger.0.bank_transactions.get("t1").unwrap().amount,
+            Decimal::new(-350, 2)
+        );
+    }
+
+    #[test]
+    fn rollback_waits_for_later_batches_with_the_same_transactions() {
+        let mut ledger = TestLedger::new("dependent");
+        let first = ledger.stage(0, vec![transaction("t1", -350, 650)]);
+        ledger.0.commit_import_batch(&first).unwrap();
+        let second = ledger.stage(
+            1,
+            vec![transaction("t1", -350, 650), transaction("t2", -100, 550)],
+        );
+        ledger.0.commit_import_batch(&second).unwrap();
+
+        assert!(matches!(
+            ledger.0.rollback_import_batch(&first),
+            Err(Error::ImportBatchHasDependents { dependent_batch_id, .. }) if dependent_batch_id == second
+        ));
+
+        assert_eq!(ledger.0.rollback_import_batch(&second).unwrap(), 1);
+        assert!(ledger.0.bank_transactions.get("t2").is_none());
+        assert_eq!(ledger.0.rollback_import_batch(&first).unwrap(), 1);
+        assert!(ledger.0.bank_transactions.is_empty());
+        assert!(ledger.0.journal.is_empty());
+    }
+}
@@ -7,6 +7,12 @@ pub use bank_formats::*;
 mod bank_statements;
 pub use bank_statements::*;
 
+mod bank_transactions_repository;
+pub use bank_transactions_repository::*;
+
+mod import_batches_repository;
+pub use import_batches_repository::*;
+
 mod accounts;
 pub use accounts::*;
 
@@ -16,6 +22,9 @@ pub use cost_centers_repository::*;
 mod accounts_repository;
 pub use accounts_repository::*;
 
+mod bank_formats_repository;
+pub use bank_formats_repository::*;
+
 mod relations_repository;
 pub use relations_repository::*;
 
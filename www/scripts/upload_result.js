@@ -83,4 +83,50 @@ const assigned = new Amounts(total_assigned, general);
 calculate("imported", imported);
 calculate("assigned", assigned);
 
-general.update();
\ No newline at end of file
+general.update();
+
+function assignedGroup(group) {
+    let assigned = document.querySelector(".assigned");
+    if (!assigned) {
+        assigned = document.createElement("div");
+        assigned.className = "assigned";
+        document.querySelector("article").appendChild(assigned);
+    }
+
+    const existing = Array.prototype.slice.call(assigned.getElementsByClassName("category-header"))
+        .find(header => header.dataset.group == group);
+    if (existing) return existing;
+
+    const header = document.createElement("div");
+    header.className = "category-header";
+    header.dataset.group = group;
+    const title = document.createElement("p");
+    title.innerText = group;
+    header.appendChild(title);
+    assigned.appendChild(header);
+    return header;
+}
+
+document.querySelectorAll("form.manual-assignment").forEach(form => {
+    form.addEventListener("submit", event => {
+        event.preventDefault();
+        fetch(form.action, {
+            method: "POST",
+            body: new URLSearchParams(new FormData(form)),
+        })
+            .then(response => response.text())
+            .then(responseText => {
+                if (responseText.startsWith("Error:")) {
+                    console.error(responseText);
+                    alert(responseText);
+                    return;
+                }
+
+                const template = document.createElement("template");
+                template.innerHTML = responseText.trim();
+                const row = template.content.firstElementChild;
+                form.closest(".transaction").remove();
+                assignedGroup(row.dataset.group).appendChild(row);
+            });
+    });
+});